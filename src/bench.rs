@@ -0,0 +1,176 @@
+use crate::executor::{ExecutionResult, Executor, TaskSettings};
+use crate::pool::ThreadPool;
+use crate::task::TaskType;
+use std::time::Duration;
+
+/// How many times a benchmark is run before and during measurement.
+#[derive(Clone, Copy, Debug)]
+pub struct BenchConfig {
+    /// Untimed runs used to warm caches, the allocator and the thread pool.
+    pub warmup: u32,
+    /// Timed runs that make up the reported statistics (at least 1).
+    pub repetitions: u32,
+}
+
+/// Summary statistics over the measured durations of one execution mode.
+#[derive(Clone, Debug)]
+pub struct Stats {
+    /// Every measured duration, in the order it was recorded.
+    pub samples: Vec<Duration>,
+    pub mean: Duration,
+    pub median: Duration,
+    /// Sample standard deviation (divides by `n - 1`).
+    pub std_dev: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// Lower and upper bound of the 95% confidence interval for the mean.
+    pub ci95: (Duration, Duration),
+}
+
+/// The result of benchmarking one execution mode.
+pub struct Measurement {
+    pub stats: Stats,
+    /// The last measured run, kept so its outputs can be verified and summarized.
+    pub last: ExecutionResult,
+}
+
+/// Runs `run` `config.warmup` times without timing it, then `config.repetitions` times
+/// recording the duration of each run.
+///
+/// # Arguments
+/// * `config` - Number of warmup and measured repetitions.
+/// * `run` - Executes the batch once and returns its result.
+///
+/// # Returns
+/// A `Measurement` with statistics over all measured runs and the last run's result.
+pub fn benchmark<F: FnMut() -> ExecutionResult>(config: &BenchConfig, mut run: F) -> Measurement {
+    for _ in 0..config.warmup {
+        run();
+    }
+
+    let mut samples = Vec::with_capacity(config.repetitions as usize);
+    let mut last = run();
+    samples.push(last.duration);
+    for _ in 1..config.repetitions {
+        last = run();
+        samples.push(last.duration);
+    }
+
+    Measurement { stats: Stats::from_samples(samples), last }
+}
+
+/// Benchmarks one concurrent executor on `tasks`.
+///
+/// For `Executor::Pool` the pool is started once before warmup and reused for every run,
+/// so thread start-up cost is not part of the measurement.
+pub fn measure_executor(
+    tasks: &[TaskType],
+    executor: Executor,
+    thread_count: u32,
+    settings: TaskSettings,
+    config: &BenchConfig,
+) -> Measurement {
+    match executor {
+        Executor::Pool => {
+            let mut pool = ThreadPool::new(thread_count, settings);
+            benchmark(config, || pool.execute(tasks))
+        }
+        _ => benchmark(config, || executor.execute(tasks, thread_count, settings.clone())),
+    }
+}
+
+impl Stats {
+    /// Computes summary statistics over a non-empty set of samples.
+    pub fn from_samples(samples: Vec<Duration>) -> Stats {
+        assert!(!samples.is_empty(), "Cannot compute statistics without samples");
+        let n = samples.len();
+        let secs: Vec<f64> = samples.iter().map(Duration::as_secs_f64).collect();
+
+        let mean = secs.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+        let std_dev = variance.sqrt();
+
+        let mut sorted = samples.clone();
+        sorted.sort();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        } else {
+            sorted[n / 2]
+        };
+
+        // Half-width of the interval: t * s / sqrt(n)
+        let margin = t_critical_95(n.saturating_sub(1)) * std_dev / (n as f64).sqrt();
+
+        Stats {
+            mean: Duration::from_secs_f64(mean),
+            median,
+            std_dev: Duration::from_secs_f64(std_dev),
+            min: sorted[0],
+            max: sorted[n - 1],
+            ci95: (
+                Duration::from_secs_f64((mean - margin).max(0.0)),
+                Duration::from_secs_f64(mean + margin),
+            ),
+            samples,
+        }
+    }
+}
+
+/// Two-sided 95% critical value of Student's t distribution.
+///
+/// Exact table values are used for up to 30 degrees of freedom; beyond that the
+/// distribution is close enough to normal to use 1.96.
+pub fn t_critical_95(degrees_of_freedom: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+        2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+        2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+    match degrees_of_freedom {
+        // A single sample has no spread to estimate, so the interval collapses to the mean
+        0 => 0.0,
+        df if df <= TABLE.len() => TABLE[df - 1],
+        _ => 1.96,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|ms| Duration::from_millis(*ms)).collect()
+    }
+
+    fn assert_close(actual: Duration, expected_ms: f64) {
+        let diff = (actual.as_secs_f64() * 1000.0 - expected_ms).abs();
+        assert!(diff < 1e-3, "expected {} ms, got {:?}", expected_ms, actual);
+    }
+
+    #[test]
+    fn test_stats_from_samples() {
+        let stats = Stats::from_samples(millis(&[4, 2, 8, 6]));
+        assert_close(stats.mean, 5.0);
+        assert_eq!(stats.median, Duration::from_millis(5));
+        assert_eq!(stats.min, Duration::from_millis(2));
+        assert_eq!(stats.max, Duration::from_millis(8));
+        // Sample variance is 20/3 ms², so the standard deviation is about 2.582 ms
+        assert_close(stats.std_dev, 2.58199);
+        // 3.182 * 2.582 / 2 ≈ 4.108 ms either side of the mean
+        assert_close(stats.ci95.0, 0.89205);
+        assert_close(stats.ci95.1, 9.10795);
+    }
+
+    #[test]
+    fn test_single_sample() {
+        let stats = Stats::from_samples(millis(&[3]));
+        assert_eq!(stats.median, Duration::from_millis(3));
+        assert_eq!(stats.std_dev, Duration::ZERO);
+        assert_close(stats.ci95.0, 3.0);
+        assert_close(stats.ci95.1, 3.0);
+    }
+}
@@ -0,0 +1,120 @@
+//! Cooperative cancellation and deadlines for tasks.
+//!
+//! A `CancellationToken` is handed to every `Task::run`. Long-running code calls `check`
+//! periodically and stops with `TaskError::Timeout` once its deadline has passed, or with
+//! `TaskError::Cancelled` once the token has been cancelled.
+
+use crate::task::TaskError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A shareable cancellation flag with an optional deadline.
+///
+/// Clones and children share the flag, so cancelling a batch's token cancels the tokens
+/// of every task in it. Each child can carry a tighter deadline of its own.
+///
+/// A token also remembers when it was created, and children inherit that instant, so every
+/// task can place itself on the timeline of the batch it belongs to.
+#[derive(Clone, Debug)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    started: Instant,
+}
+
+impl Default for CancellationToken {
+    fn default() -> CancellationToken {
+        CancellationToken::with_timeout(None)
+    }
+}
+
+impl CancellationToken {
+    /// A token that is never cancelled and has no deadline.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// A token whose deadline is `timeout` from now, or none if `timeout` is `None`.
+    pub fn with_timeout(timeout: Option<Duration>) -> CancellationToken {
+        let started = Instant::now();
+        CancellationToken { cancelled: Arc::default(), deadline: timeout.map(|timeout| started + timeout), started }
+    }
+
+    /// A token sharing this token's flag, whose deadline is the earlier of this token's
+    /// and `timeout` from now.
+    pub fn child(&self, timeout: Option<Duration>) -> CancellationToken {
+        let own = timeout.map(|timeout| Instant::now() + timeout);
+        let deadline = match (self.deadline, own) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        CancellationToken { cancelled: Arc::clone(&self.cancelled), deadline, started: self.started }
+    }
+
+    /// When the token, or the batch token it is a child of, was created.
+    pub fn started(&self) -> Instant {
+        self.started
+    }
+
+    /// Cancels this token, its clones and all of their children.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Time left until the deadline; `None` if there is no deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether work guarded by this token may continue.
+    ///
+    /// # Returns
+    /// * `Err(TaskError::Cancelled)` if the token was cancelled.
+    /// * `Err(TaskError::Timeout)` if the deadline has passed.
+    /// * `Ok(())` otherwise.
+    pub fn check(&self) -> Result<(), TaskError> {
+        if self.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(TaskError::Timeout),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadlines() {
+        assert_eq!(CancellationToken::new().check(), Ok(()));
+        assert_eq!(CancellationToken::new().remaining(), None);
+
+        let expired = CancellationToken::with_timeout(Some(Duration::ZERO));
+        assert_eq!(expired.check(), Err(TaskError::Timeout));
+        // A child can never outlive its parent's deadline
+        assert_eq!(expired.child(Some(Duration::from_secs(60))).check(), Err(TaskError::Timeout));
+
+        let batch = CancellationToken::with_timeout(Some(Duration::from_secs(60)));
+        let task = batch.child(Some(Duration::ZERO));
+        assert_eq!(task.check(), Err(TaskError::Timeout));
+        assert_eq!(batch.check(), Ok(()));
+        assert!(batch.child(None).remaining().unwrap() > Duration::from_secs(59));
+    }
+
+    #[test]
+    fn test_cancel_reaches_children() {
+        let batch = CancellationToken::new();
+        let task = batch.child(Some(Duration::from_secs(60)));
+        batch.clone().cancel();
+        assert!(task.is_cancelled());
+        assert_eq!(task.check(), Err(TaskError::Cancelled));
+    }
+}
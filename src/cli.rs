@@ -0,0 +1,451 @@
+use cs354_rust::bench::BenchConfig;
+use cs354_rust::executor::{Executor, TaskSettings, DEFAULT_AGING, DEFAULT_CHUNK_SIZE};
+use cs354_rust::history::DEFAULT_HISTORY_PATH;
+use cs354_rust::load::{parse_duration, LoadModel, Wait};
+use cs354_rust::priority::Priorities;
+use cs354_rust::report::OutputFormat;
+use cs354_rust::retry::RetryPolicy;
+use cs354_rust::workload::{WorkloadManifest, WorkloadSpec};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Seed used for task generation when none is given.
+pub const DEFAULT_SEED: u64 = 42;
+
+/// Batch size used when `--tasks` is not given.
+const DEFAULT_BATCH_SIZE: u32 = 1000;
+
+/// Usage text printed for `--help` and after argument errors.
+pub const USAGE: &str = "\
+Usage: CS354_Rust [COMMAND] [OPTIONS]
+
+Runs interactively when no arguments are given.
+
+Commands:
+  run      Run the batch once serially and with each executor (default)
+  bench    Benchmark with warmup and repeated measurements
+  sweep    Benchmark every thread count and estimate scaling
+  compare  Benchmark and flag significant regressions against the --history baseline
+  dag      Run the task graph in the --graph file on one worker and on --threads workers
+  generate Write the batch to the --write-tasks file without running it
+
+Options:
+  --mode <default|simulate>    default: no simulated load; simulate: same as --load fixed:100us [default: default]
+  --load <MODEL>               Simulated delay before every task: none, fixed:D, uniform:D:D, exp:D or
+                               lognormal:D:SIGMA, with durations such as 100us or 1.5ms [default: none]
+  --spin                       Busy-spin through the simulated delay instead of sleeping (CPU-bound load)
+  --timeout <D>                Fail any task still running D after it started, e.g. 5ms [default: none]
+  --batch-timeout <D>          Stop running tasks and cancel queued ones D after a batch starts [default: none]
+  --flaky <P>                  Fail each task with a transient error with probability P, e.g. 0.05 [default: 0]
+  --retry <POLICY>             Retry transient failures (timeouts and --flaky): N attempts, N:fixed:D,
+                               or N:exp:D[:MAX] for exponential backoff [default: 1, no retries]
+  --tasks <N>                  Number of tasks to generate [default: 1000]
+  --threads <N[,N...]>         Thread count; sweep accepts a list [default: all CPUs, sweep: 1..=CPUs]
+  --seed <N>                   Seed for task generation [default: 42]
+  --mix <MIX>                  Weighted task mix, e.g. prime_check:80:n=1..10000000,fibonacci:20
+  --spec <FILE>                Read the task mix from a JSON workload spec [default: uniform mix]
+  --workload <FILE>            Regenerate the batch described by a manifest (overrides --tasks/--seed/--mix)
+  --manifest <FILE>            Write the manifest of the generated batch to FILE
+  --input <FILE>               Run the tasks in a .json or .csv file instead of generating them
+  --graph <FILE>               Task graph for the dag command: JSON tasks whose inputs come from other tasks
+  --write-tasks <FILE>         Write the batch to a .json or .csv file
+  --executor <NAME>            mutex, work-stealing, channel, atomic, pool, priority or all [default: all]
+  --chunk-size <N>             Tasks the atomic executor claims at once [default: 1]
+  --priority <MAP>             Task priorities for the priority and dag executors, e.g. compute:high,prime_check:low
+                               (low, normal or high) [default: all normal]
+  --aging <D>                  Wait after which the priority executor raises a task a level [default: 10ms]
+  --warmup <N>                 Untimed runs before measuring [default: run 0, otherwise 1]
+  --repetitions <N>            Measured runs [default: run 1, bench/compare 10, sweep 5]
+  --format <text|json|csv>     Print a human-readable summary or a machine-readable report [default: text]
+  --report <FILE>              Write the report to a .json file or append it to a .csv file
+  --history <FILE>             Append run/bench results to FILE; compare reads its baseline from it
+                               [default for compare: bench-history.jsonl]
+  --trace <FILE>               Write a Chrome trace of the serial and mutex queue (or dag) runs to FILE
+  --latency                    Print per-task latency percentiles and histograms [default: on for run]
+  -h, --help                   Print this help";
+
+/// What the program was asked to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Compare serial execution with each executor at one thread count.
+    Run,
+    /// Like `Run`, but with warmup and several measured repetitions by default.
+    Bench,
+    /// Benchmark each executor over a range of thread counts.
+    Sweep,
+    /// Like `Bench`, then compare the results with the latest matching run in the history.
+    Compare,
+    /// Run a task graph on one worker and on the DAG executor.
+    Dag,
+    /// Write the batch to a task file without running it.
+    Generate,
+    /// Print usage and exit.
+    Help,
+}
+
+/// Everything needed to run a benchmark, whether it came from flags or prompts.
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub command: Command,
+    /// Simulated load and deadlines applied to every task.
+    pub settings: TaskSettings,
+    /// The batch to generate.
+    pub workload: WorkloadManifest,
+    /// Where to write the workload manifest, if anywhere.
+    pub manifest_path: Option<PathBuf>,
+    /// A task file to run instead of generating `workload`.
+    pub input_path: Option<PathBuf>,
+    /// The task graph run by the dag command.
+    pub graph_path: Option<PathBuf>,
+    /// Where to write the batch as a task file, if anywhere.
+    pub tasks_path: Option<PathBuf>,
+    /// What to print to stdout: human-readable text, or only the machine-readable report.
+    pub format: OutputFormat,
+    /// Where to write the machine-readable report, if anywhere, and in which format.
+    pub report: Option<(PathBuf, OutputFormat)>,
+    /// The history file that run and bench append to and compare reads from, if any.
+    pub history_path: Option<PathBuf>,
+    /// Where to write a Chrome trace of the serial and mutex queue (or dag) runs, if anywhere.
+    pub trace_path: Option<PathBuf>,
+    /// Whether to print per-task latency reports after each mode.
+    pub latency: bool,
+    /// A single entry for `run` and `bench`; every point to measure for `sweep`.
+    pub thread_counts: Vec<u32>,
+    pub executors: Vec<Executor>,
+    pub config: BenchConfig,
+}
+
+/// Parses command-line arguments (without the program name) into `Options`.
+///
+/// # Returns
+/// The parsed options, or a message describing the first invalid argument.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut args = args.into_iter().peekable();
+
+    let command = match args.peek().map(String::as_str) {
+        Some("run") => Command::Run,
+        Some("bench") => Command::Bench,
+        Some("sweep") => Command::Sweep,
+        Some("compare") => Command::Compare,
+        Some("dag") => Command::Dag,
+        Some("generate") => Command::Generate,
+        Some("help") => Command::Help,
+        Some(other) if !other.starts_with('-') => return Err(format!("Unknown command '{}'.", other)),
+        _ => Command::Run,
+    };
+    if args.peek().is_some_and(|arg| !arg.starts_with('-')) {
+        args.next();
+    }
+
+    let max_threads = num_cpus::get() as u32;
+    let (default_warmup, default_repetitions) = match command {
+        Command::Bench | Command::Compare => (1, 10),
+        Command::Sweep => (1, 5),
+        _ => (0, 1),
+    };
+
+    let mut options = Options {
+        command,
+        settings: TaskSettings::DEFAULT,
+        workload: WorkloadManifest {
+            seed: DEFAULT_SEED,
+            batch_size: DEFAULT_BATCH_SIZE,
+            spec: WorkloadSpec::uniform(),
+        },
+        manifest_path: None,
+        input_path: None,
+        graph_path: None,
+        tasks_path: None,
+        format: OutputFormat::Text,
+        report: None,
+        history_path: None,
+        trace_path: None,
+        latency: command == Command::Run,
+        thread_counts: match command {
+            Command::Sweep => (1..=max_threads).collect(),
+            _ => vec![max_threads],
+        },
+        executors: Executor::ALL.to_vec(),
+        config: BenchConfig { warmup: default_warmup, repetitions: default_repetitions },
+    };
+    let mut chunk_size = DEFAULT_CHUNK_SIZE;
+    let mut aging = DEFAULT_AGING;
+    let mut workload_path = None;
+    let mut report_path = None;
+    let mut spin = false;
+    let mut failure_rate = 0.0;
+
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            options.command = Command::Help;
+            continue;
+        }
+        if flag == "--latency" {
+            options.latency = true;
+            continue;
+        }
+        if flag == "--spin" {
+            spin = true;
+            continue;
+        }
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for '{}'.", flag));
+        match flag.as_str() {
+            "--mode" => {
+                options.settings.load = match value()?.as_str() {
+                    "default" => LoadModel::NONE,
+                    "simulate" => LoadModel::SIMULATED,
+                    other => return Err(format!("Unknown mode '{}'; expected default or simulate.", other)),
+                }
+            }
+            "--load" => options.settings.load = LoadModel::parse(&value()?)?,
+            "--timeout" => options.settings.timeout = Some(parse_timeout(&flag, &value()?)?),
+            "--batch-timeout" => options.settings.batch_timeout = Some(parse_timeout(&flag, &value()?)?),
+            "--flaky" => {
+                let raw = value()?;
+                failure_rate = raw.parse::<f64>().ok().filter(|p| (0.0..=1.0).contains(p)).ok_or_else(|| {
+                    format!("Invalid value '{}' for '{}'; expected a probability between 0 and 1.", raw, flag)
+                })?;
+            }
+            "--retry" => options.settings.retry = RetryPolicy::parse(&value()?)?,
+            "--tasks" => options.workload.batch_size = parse_positive(&flag, &value()?)?,
+            "--threads" => {
+                let raw = value()?;
+                options.thread_counts = parse_thread_counts(&raw).ok_or_else(|| {
+                    format!("Invalid value '{}' for '{}'; expected positive integers such as 1,2,4.", raw, flag)
+                })?;
+            }
+            "--seed" => {
+                let raw = value()?;
+                options.workload.seed = raw.parse().map_err(|_| format!("Invalid value '{}' for '{}'.", raw, flag))?;
+            }
+            "--mix" => options.workload.spec = WorkloadSpec::parse_mix(&value()?)?,
+            "--spec" => options.workload.spec = WorkloadSpec::load(&PathBuf::from(value()?))?,
+            "--workload" => workload_path = Some(PathBuf::from(value()?)),
+            "--manifest" => options.manifest_path = Some(PathBuf::from(value()?)),
+            "--input" => options.input_path = Some(PathBuf::from(value()?)),
+            "--graph" => options.graph_path = Some(PathBuf::from(value()?)),
+            "--write-tasks" => options.tasks_path = Some(PathBuf::from(value()?)),
+            "--format" => options.format = OutputFormat::parse(&value()?)?,
+            "--report" => report_path = Some(PathBuf::from(value()?)),
+            "--history" => options.history_path = Some(PathBuf::from(value()?)),
+            "--trace" => options.trace_path = Some(PathBuf::from(value()?)),
+            "--executor" => options.executors = parse_executors(&value()?)?,
+            "--chunk-size" => chunk_size = parse_positive(&flag, &value()?)? as usize,
+            "--priority" => options.settings.priorities = Priorities::parse(&value()?)?,
+            "--aging" => aging = parse_timeout(&flag, &value()?)?,
+            "--warmup" => {
+                let raw = value()?;
+                options.config.warmup = raw.parse().map_err(|_| format!("Invalid value '{}' for '{}'.", raw, flag))?;
+            }
+            "--repetitions" => options.config.repetitions = parse_positive(&flag, &value()?)?,
+            other => return Err(format!("Unknown option '{}'.", other)),
+        }
+    }
+
+    if options.command == Command::Generate && options.tasks_path.is_none() {
+        return Err("The generate command needs --write-tasks <FILE>.".into());
+    }
+    if (options.command == Command::Dag) != options.graph_path.is_some() && options.command != Command::Help {
+        return Err("The dag command needs --graph <FILE>, and only the dag command uses it.".into());
+    }
+    if options.input_path.is_some() && options.manifest_path.is_some() {
+        return Err("A manifest describes a generated batch and cannot be written for --input.".into());
+    }
+    if options.command != Command::Sweep && options.thread_counts.len() != 1 {
+        return Err("Only the sweep command accepts a list of thread counts.".into());
+    }
+    if spin {
+        if options.settings.load.is_none() {
+            return Err("--spin needs a simulated load from --load or --mode simulate.".into());
+        }
+        options.settings.load = options.settings.load.with_wait(Wait::Spin);
+    }
+    options.settings.load = options.settings.load.with_failure_rate(failure_rate);
+    if options.history_path.is_some() && !matches!(options.command, Command::Run | Command::Bench | Command::Compare | Command::Help) {
+        return Err("Only the run, bench and compare commands use --history.".into());
+    }
+    if options.trace_path.is_some() && matches!(options.command, Command::Sweep | Command::Generate) {
+        return Err("Only the run, bench, compare and dag commands use --trace.".into());
+    }
+    if options.command == Command::Compare {
+        if options.input_path.is_some() {
+            return Err("Baselines are keyed by workload manifest, so compare cannot use --input.".into());
+        }
+        options.history_path.get_or_insert_with(|| PathBuf::from(DEFAULT_HISTORY_PATH));
+    }
+    if (options.format != OutputFormat::Text || report_path.is_some())
+        && !matches!(options.command, Command::Run | Command::Bench | Command::Help)
+    {
+        return Err("Reports are only produced by the run and bench commands.".into());
+    }
+    if let Some(path) = report_path {
+        // An explicit --format wins over the file extension
+        let format = match options.format {
+            OutputFormat::Text => OutputFormat::from_path(&path)?,
+            format => format,
+        };
+        options.report = Some((path, format));
+    }
+    if let Some(path) = workload_path {
+        options.workload = WorkloadManifest::load(&path)?;
+    }
+    for executor in &mut options.executors {
+        match executor {
+            Executor::AtomicIndex { chunk_size: size } => *size = chunk_size,
+            Executor::Priority { aging: interval } => *interval = aging,
+            _ => {}
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the value of a flag that must be a positive integer.
+fn parse_positive(flag: &str, raw: &str) -> Result<u32, String> {
+    match raw.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("Invalid value '{}' for '{}'; expected a positive integer.", raw, flag)),
+    }
+}
+
+/// Parses the value of a flag that must be a positive duration such as `5ms`.
+fn parse_timeout(flag: &str, raw: &str) -> Result<Duration, String> {
+    match parse_duration(raw) {
+        Some(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err(format!("Invalid value '{}' for '{}'; expected a duration such as 500us, 5ms or 2s.", raw, flag)),
+    }
+}
+
+/// Parses a comma-separated list of positive thread counts.
+///
+/// # Returns
+/// `None` if any entry is not a positive integer.
+pub fn parse_thread_counts(input: &str) -> Option<Vec<u32>> {
+    input
+        .split(',')
+        .map(|part| part.trim().parse::<u32>().ok().filter(|&n| n > 0))
+        .collect()
+}
+
+/// Parses an `--executor` value: one executor key or `all`.
+fn parse_executors(raw: &str) -> Result<Vec<Executor>, String> {
+    if raw == "all" {
+        return Ok(Executor::ALL.to_vec());
+    }
+    Executor::ALL
+        .iter()
+        .find(|executor| executor.key() == raw)
+        .map(|executor| vec![*executor])
+        .ok_or_else(|| format!("Unknown executor '{}'.", raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_bench_flags() {
+        let options = parse(&[
+            "bench", "--mode", "simulate", "--tasks", "500", "--threads", "2", "--seed", "7",
+            "--executor", "atomic", "--chunk-size", "16", "--warmup", "0", "--repetitions", "3",
+        ])
+        .unwrap();
+        assert_eq!(options.command, Command::Bench);
+        assert_eq!(options.settings.load, LoadModel::SIMULATED);
+        assert_eq!(options.workload.batch_size, 500);
+        assert_eq!(options.thread_counts, vec![2]);
+        assert_eq!(options.workload.seed, 7);
+        assert_eq!(options.workload.spec, WorkloadSpec::uniform());
+        assert_eq!(options.executors, vec![Executor::AtomicIndex { chunk_size: 16 }]);
+        assert_eq!(options.config.warmup, 0);
+        assert_eq!(options.config.repetitions, 3);
+        assert!(!options.latency);
+    }
+
+    #[test]
+    fn test_defaults_depend_on_command() {
+        let run = parse(&["--tasks", "10"]).unwrap();
+        assert_eq!(run.command, Command::Run);
+        assert_eq!((run.config.warmup, run.config.repetitions), (0, 1));
+        assert_eq!(run.executors, Executor::ALL.to_vec());
+        assert!(run.latency);
+        assert_eq!(run.trace_path, None);
+
+        let traced = parse(&["--trace", "trace.json"]).unwrap();
+        assert_eq!(traced.trace_path, Some(PathBuf::from("trace.json")));
+
+        let reported = parse(&["bench", "--report", "runs.csv"]).unwrap();
+        assert_eq!(reported.format, OutputFormat::Text);
+        assert_eq!(reported.report, Some((PathBuf::from("runs.csv"), OutputFormat::Csv)));
+        let json = parse(&["--format", "json", "--report", "runs.txt"]).unwrap();
+        assert_eq!(json.report, Some((PathBuf::from("runs.txt"), OutputFormat::Json)));
+
+        let mixed = parse(&["--mix", "prime_check:80:n=1..10000000,fibonacci:20"]).unwrap();
+        assert_eq!(mixed.workload.spec.variants.len(), 2);
+
+        let sweep = parse(&["sweep", "--threads", "1,2,4"]).unwrap();
+        assert_eq!(sweep.thread_counts, vec![1, 2, 4]);
+        assert_eq!((sweep.config.warmup, sweep.config.repetitions), (1, 5));
+
+        let spinning = parse(&["--spin", "--load", "exp:50us"]).unwrap();
+        assert_eq!(spinning.settings.load, LoadModel::parse("exp:50us").unwrap().with_wait(Wait::Spin));
+        assert_eq!(run.settings.load, LoadModel::NONE);
+
+        let timed = parse(&["--timeout", "5ms", "--batch-timeout", "2s"]).unwrap();
+        assert_eq!(timed.settings.timeout, Some(Duration::from_millis(5)));
+        assert_eq!(timed.settings.batch_timeout, Some(Duration::from_secs(2)));
+        assert_eq!(run.settings.timeout, None);
+
+        let flaky = parse(&["--flaky", "0.1", "--load", "fixed:1ms", "--retry", "3:exp:1ms"]).unwrap();
+        assert_eq!(flaky.settings.load, LoadModel::parse("fixed:1ms").unwrap().with_failure_rate(0.1));
+        assert_eq!(flaky.settings.retry.max_attempts, 3);
+        assert_eq!(run.settings.retry, RetryPolicy::NONE);
+
+        let prioritized = parse(&["--executor", "priority", "--priority", "compute:high", "--aging", "2ms"]).unwrap();
+        assert_eq!(prioritized.executors, vec![Executor::Priority { aging: Duration::from_millis(2) }]);
+        assert_eq!(prioritized.settings.priorities, Priorities::parse("compute:high").unwrap());
+
+        let dag = parse(&["dag", "--graph", "jobs.json", "--threads", "2"]).unwrap();
+        assert_eq!(dag.command, Command::Dag);
+        assert_eq!(dag.graph_path, Some(PathBuf::from("jobs.json")));
+
+        let compare = parse(&["compare"]).unwrap();
+        assert_eq!(compare.command, Command::Compare);
+        assert_eq!(compare.history_path, Some(PathBuf::from(DEFAULT_HISTORY_PATH)));
+        assert_eq!((compare.config.warmup, compare.config.repetitions), (1, 10));
+        assert!(!compare.latency);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&["launch"]).is_err());
+        assert!(parse(&["--tasks"]).is_err());
+        assert!(parse(&["--tasks", "0"]).is_err());
+        assert!(parse(&["--executor", "fastest"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["--mix", "prime_check"]).is_err());
+        assert!(parse(&["generate"]).is_err());
+        assert!(parse(&["--input", "tasks.csv", "--manifest", "m.json"]).is_err());
+        assert!(parse(&["run", "--threads", "1,2"]).is_err());
+        assert!(parse(&["--format", "xml"]).is_err());
+        assert!(parse(&["--load", "fixed:10"]).is_err());
+        assert!(parse(&["--timeout", "0ms"]).is_err());
+        assert!(parse(&["--batch-timeout", "soon"]).is_err());
+        assert!(parse(&["--flaky", "1.5"]).is_err());
+        assert!(parse(&["--retry", "0"]).is_err());
+        assert!(parse(&["--priority", "compute:urgent"]).is_err());
+        assert!(parse(&["dag"]).is_err());
+        assert!(parse(&["run", "--graph", "jobs.json"]).is_err());
+        assert!(parse(&["--spin"]).is_err());
+        assert!(parse(&["--report", "runs.txt"]).is_err());
+        assert!(parse(&["sweep", "--format", "json"]).is_err());
+        assert!(parse(&["sweep", "--history", "h.jsonl"]).is_err());
+        assert!(parse(&["sweep", "--trace", "trace.json"]).is_err());
+        assert!(parse(&["generate", "--write-tasks", "t.csv", "--trace", "trace.json"]).is_err());
+        assert!(parse(&["compare", "--input", "tasks.csv"]).is_err());
+        assert_eq!(parse(&["--help"]).unwrap().command, Command::Help);
+    }
+}
@@ -0,0 +1,447 @@
+//! Task dependency graphs and the DAG executor.
+//!
+//! A `TaskGraph` is a set of tasks where some parameters are filled in from other tasks'
+//! outputs, e.g. a `ModuloExponentiation` whose exponent is a `Fibonacci` result. Graphs are
+//! read from JSON files such as:
+//!
+//! ```json
+//! { "tasks": [
+//!     { "id": "fib", "task": { "type": "fibonacci", "n": 20 } },
+//!     { "id": "pow", "task": { "type": "modulo_exponentiation", "base": 2, "exponent": 0, "modulus": 1000 },
+//!       "inputs": { "exponent": "fib" }, "priority": "high" }
+//! ] }
+//! ```
+//!
+//! A task's optional `priority` overrides the priority `--priority` gives its kind.
+//!
+//! `execute_dag` runs a task as soon as every task it depends on has finished. A task whose
+//! dependency failed is not run and fails with `TaskError::UpstreamFailed` instead, which in
+//! turn fails everything downstream of it.
+
+use crate::cancel::CancellationToken;
+use crate::executor::{lock_timed, run_with_retries, ExecutionResult, TaskSettings, TaskTiming, WorkerStats, DEFAULT_AGING};
+use crate::priority::{MultiLevelQueue, Priorities, Priority};
+use crate::task::{TaskError, TaskOutput, TaskType};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Instant;
+
+/// One task in a graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    /// Name used to refer to the task in the graph file and in error messages.
+    pub id: String,
+    /// The task, with placeholder values for the parameters filled from `inputs`.
+    pub task: TaskType,
+    /// Each parameter filled from another task's output, and the index of that task.
+    pub inputs: Vec<(String, usize)>,
+    /// The priority the task set for itself, if any; otherwise its kind's is used.
+    pub priority: Option<Priority>,
+}
+
+/// A validated, acyclic set of tasks and the dependencies between them.
+#[derive(Clone, Debug)]
+pub struct TaskGraph {
+    nodes: Vec<Node>,
+    /// For every node, the nodes that take one of their inputs from it.
+    dependents: Vec<Vec<usize>>,
+}
+
+/// A node as written in a graph file, with dependencies referred to by id.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NodeSpec {
+    id: String,
+    task: TaskType,
+    #[serde(default)]
+    inputs: BTreeMap<String, String>,
+    #[serde(default)]
+    priority: Option<Priority>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GraphSpec {
+    tasks: Vec<NodeSpec>,
+}
+
+impl TaskGraph {
+    /// Builds a graph after checking that every input names an existing task and a
+    /// parameter of the task it feeds, and that there are no dependency cycles.
+    pub fn new(nodes: Vec<Node>) -> Result<TaskGraph, String> {
+        let mut dependents = vec![Vec::new(); nodes.len()];
+        for (index, node) in nodes.iter().enumerate() {
+            for (position, (param, from)) in node.inputs.iter().enumerate() {
+                if node.inputs[..position].iter().any(|(earlier, _)| earlier == param) {
+                    return Err(format!("Task '{}' has more than one input for '{}'.", node.id, param));
+                }
+                if *from >= nodes.len() {
+                    return Err(format!("Task '{}' depends on task {}, which does not exist.", node.id, from));
+                }
+                bind(&node.task, param, &TaskOutput::Integer(0))
+                    .map_err(|_| format!("Task '{}' has no parameter '{}' to take an input into.", node.id, param))?;
+                dependents[*from].push(index);
+            }
+        }
+        let graph = TaskGraph { nodes, dependents };
+        if let Err(cycle) = graph.topological_order() {
+            let ids: Vec<&str> = cycle.iter().map(|&index| graph.nodes[index].id.as_str()).collect();
+            return Err(format!("The task graph has a dependency cycle among: {}.", ids.join(", ")));
+        }
+        Ok(graph)
+    }
+
+    /// Parses a graph from the JSON format described in the module documentation.
+    pub fn parse_json(text: &str) -> Result<TaskGraph, String> {
+        let spec: GraphSpec = serde_json::from_str(text).map_err(|e| format!("Invalid task graph: {}", e))?;
+        let mut positions = HashMap::new();
+        for (index, node) in spec.tasks.iter().enumerate() {
+            if positions.insert(node.id.as_str(), index).is_some() {
+                return Err(format!("Task id '{}' is used more than once.", node.id));
+            }
+        }
+        let mut nodes = Vec::with_capacity(spec.tasks.len());
+        for node in &spec.tasks {
+            let inputs = node
+                .inputs
+                .iter()
+                .map(|(param, from)| match positions.get(from.as_str()) {
+                    Some(&index) => Ok((param.clone(), index)),
+                    None => Err(format!("Task '{}' depends on unknown task '{}'.", node.id, from)),
+                })
+                .collect::<Result<_, _>>()?;
+            nodes.push(Node { id: node.id.clone(), task: node.task.clone(), inputs, priority: node.priority });
+        }
+        TaskGraph::new(nodes)
+    }
+
+    /// Reads a graph from a JSON file.
+    pub fn load(path: &Path) -> Result<TaskGraph, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read task graph '{}': {}", path.display(), e))?;
+        TaskGraph::parse_json(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Every node's task as written, before any inputs are filled in.
+    pub fn tasks(&self) -> Vec<TaskType> {
+        self.nodes.iter().map(|node| node.task.clone()).collect()
+    }
+
+    /// The priority of every node: its own if it set one, otherwise the one `kinds` gives
+    /// its task's kind.
+    pub fn priorities(&self, kinds: &Priorities) -> Priorities {
+        kinds.with_task_priorities(self.nodes.iter().map(|node| node.priority).collect())
+    }
+
+    /// Every node's task with its inputs filled in from `outputs`, as `execute_dag` ran it.
+    ///
+    /// A task whose inputs could not be filled in, because a dependency failed, is left as
+    /// written.
+    ///
+    /// # Arguments
+    /// * `outputs` - Every node's result, in node order, such as an `ExecutionResult`'s.
+    pub fn bound_tasks(&self, outputs: &[Result<TaskOutput, TaskError>]) -> Vec<TaskType> {
+        (0..self.nodes.len())
+            .map(|index| self.bind_inputs(index, |from| &outputs[from]).unwrap_or_else(|_| self.nodes[index].task.clone()))
+            .collect()
+    }
+
+    /// The task of node `index` with each input filled in from its dependency's output.
+    ///
+    /// # Returns
+    /// The bound task, or `TaskError::UpstreamFailed` naming the first failed dependency.
+    fn bind_inputs<'a>(
+        &self,
+        index: usize,
+        output_of: impl Fn(usize) -> &'a Result<TaskOutput, TaskError>,
+    ) -> Result<TaskType, TaskError> {
+        let node = &self.nodes[index];
+        node.inputs.iter().try_fold(node.task.clone(), |task, (param, from)| match output_of(*from) {
+            Ok(value) => bind(&task, param, value),
+            Err(e) => Err(TaskError::UpstreamFailed(format!("'{}' failed: {}", self.nodes[*from].id, e))),
+        })
+    }
+
+    /// Orders the nodes so that every node comes after the nodes it depends on, using
+    /// Kahn's algorithm.
+    ///
+    /// # Returns
+    /// The order, or the nodes left on or behind a cycle if there is one.
+    fn topological_order(&self) -> Result<Vec<usize>, Vec<usize>> {
+        let mut missing: Vec<usize> = self.nodes.iter().map(|node| node.inputs.len()).collect();
+        let mut ready: VecDeque<usize> = (0..self.nodes.len()).filter(|&index| missing[index] == 0).collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(index) = ready.pop_front() {
+            order.push(index);
+            for &dependent in &self.dependents[index] {
+                missing[dependent] -= 1;
+                if missing[dependent] == 0 {
+                    ready.push_back(dependent);
+                }
+            }
+        }
+        if order.len() == self.nodes.len() {
+            Ok(order)
+        } else {
+            Err((0..self.nodes.len()).filter(|&index| missing[index] > 0).collect())
+        }
+    }
+}
+
+/// `task` with its parameter `param` set to `value`.
+///
+/// # Returns
+/// `Err(TaskError::InvalidInput)` if the task has no such parameter, or `value` is a
+/// boolean or does not fit the parameter's type.
+pub fn bind(task: &TaskType, param: &str, value: &TaskOutput) -> Result<TaskType, TaskError> {
+    let invalid = |reason: String| TaskError::InvalidInput(format!("{} for '{}' of {}", reason, param, task.key()));
+    let number = match value {
+        TaskOutput::Integer(value) => Value::from(*value),
+        TaskOutput::BigInteger(value) => Value::from(*value),
+        TaskOutput::Bool(_) => return Err(invalid("a true/false output cannot be used".into())),
+    };
+    let mut fields = match serde_json::to_value(task) {
+        Ok(Value::Object(fields)) => fields,
+        _ => return Err(invalid("parameters cannot be set".into())),
+    };
+    match fields.get_mut(param) {
+        Some(field) if param != "type" => *field = number,
+        _ => return Err(invalid("no such parameter".into())),
+    }
+    serde_json::from_value(Value::Object(fields)).map_err(|_| invalid(format!("{} is out of range", value)))
+}
+
+/// What the DAG executor's workers share.
+struct Schedule {
+    /// Nodes whose inputs are all available, with the time they became ready, by priority.
+    ready: MultiLevelQueue<(usize, Instant)>,
+    /// Number of each node's inputs whose producing task has not finished yet.
+    missing: Vec<usize>,
+    /// Each node's result once it has finished.
+    outputs: Vec<Option<Result<TaskOutput, TaskError>>>,
+    /// Nodes that have not finished yet; workers exit once it reaches zero.
+    unfinished: usize,
+}
+
+/// Executes a task graph on `thread_count` workers, running each task as soon as every
+/// task it depends on has finished.
+///
+/// Ready tasks wait in a `MultiLevelQueue` behind a mutex, at their own priority or the one
+/// `settings.priorities` gives their kind, aged up every `DEFAULT_AGING`. Idle workers sleep
+/// on a condition variable until a finishing task makes more ready or the graph is done. Each task's
+/// inputs are filled in from its dependencies' outputs before it runs, and a task with a
+/// failed dependency fails with `TaskError::UpstreamFailed` without running.
+///
+/// # Arguments
+/// * `graph` - The tasks to run and their dependencies.
+/// * `thread_count` - Number of worker threads to spawn (at least 1).
+/// * `settings` - Simulated load, timeouts, retry policy and task priorities.
+///
+/// # Returns
+/// An `ExecutionResult` in node order, where each task's wait is counted from when its
+/// last dependency finished and each attempt's start from when the batch started.
+pub fn execute_dag(graph: &TaskGraph, thread_count: u32, settings: TaskSettings) -> ExecutionResult {
+    let thread_count = thread_count.max(1);
+    let start_time = Instant::now();
+    let batch = CancellationToken::with_timeout(settings.batch_timeout);
+    let priorities = graph.priorities(&settings.priorities);
+    let priority_of = |index: usize| priorities.of(index, &graph.nodes[index].task);
+    let missing: Vec<usize> = graph.nodes.iter().map(|node| node.inputs.len()).collect();
+    let mut ready = MultiLevelQueue::new(DEFAULT_AGING);
+    for index in (0..graph.nodes.len()).filter(|&index| missing[index] == 0) {
+        ready.push((index, start_time), priority_of(index));
+    }
+    let schedule = Mutex::new(Schedule {
+        ready,
+        missing,
+        outputs: vec![None; graph.nodes.len()],
+        unfinished: graph.nodes.len(),
+    });
+    let progress = Condvar::new();
+
+    let (produced, mut workers): (Vec<_>, Vec<_>) = thread::scope(|scope| {
+        let handles: Vec<_> = (0..thread_count as usize)
+            .map(|worker| {
+                let (schedule, progress, batch, settings, priority_of) = (&schedule, &progress, &batch, &settings, &priority_of);
+                scope.spawn(move || {
+                    let mut produced = Vec::new();
+                    let mut stats = WorkerStats::default();
+                    loop {
+                        // Wait until a task is ready or every task has finished
+                        let (mut state, mut acquired) = lock_timed(schedule, &mut stats.lock);
+                        while state.ready.is_empty() && state.unfinished > 0 {
+                            stats.lock.hold += acquired.elapsed();
+                            state = progress.wait(state).unwrap();
+                            acquired = Instant::now();
+                        }
+                        let Some(((index, ready_at), _)) = state.ready.pop() else {
+                            stats.lock.hold += acquired.elapsed();
+                            break;
+                        };
+                        let task = graph.bind_inputs(index, |from| {
+                            state.outputs[from].as_ref().expect("Dependencies finish first")
+                        });
+                        drop(state);
+                        stats.lock.hold += acquired.elapsed();
+
+                        let (result, mut timing) = match task {
+                            Ok(task) => run_with_retries(&task, settings, batch, ready_at),
+                            Err(e) => (Err(e), TaskTiming { wait: ready_at.elapsed(), ..TaskTiming::default() }),
+                        };
+                        timing.ran_on(worker);
+                        stats.tasks += 1;
+                        stats.errors += result.is_err() as usize;
+                        stats.busy += timing.run;
+
+                        // Publish the result and release every dependent it was the last input of
+                        let (mut state, acquired) = lock_timed(schedule, &mut stats.lock);
+                        state.outputs[index] = Some(result.clone());
+                        state.unfinished -= 1;
+                        let now = Instant::now();
+                        for &dependent in &graph.dependents[index] {
+                            state.missing[dependent] -= 1;
+                            if state.missing[dependent] == 0 {
+                                state.ready.push((dependent, now), priority_of(dependent));
+                            }
+                        }
+                        drop(state);
+                        stats.lock.hold += acquired.elapsed();
+                        progress.notify_all();
+                        produced.push((index, result, timing));
+                    }
+                    (produced, stats)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().expect("Thread panicked during execution")).unzip()
+    });
+    let duration = start_time.elapsed();
+
+    for stats in &mut workers {
+        stats.idle = duration.saturating_sub(stats.busy + stats.lock.wait + stats.lock.hold);
+    }
+    let mut result = ExecutionResult::from_records(duration, produced.into_iter().flatten().collect());
+    result.workers = workers;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small graph with data flowing along two chains, one of which fails at its root.
+    fn sample_graph() -> TaskGraph {
+        TaskGraph::parse_json(
+            r#"{ "tasks": [
+                { "id": "fib", "task": { "type": "fibonacci", "n": 10 } },
+                { "id": "pow", "task": { "type": "modulo_exponentiation", "base": 2, "exponent": 0, "modulus": 1000 },
+                  "inputs": { "exponent": "fib" } },
+                { "id": "sum", "task": { "type": "compute", "a": 0, "b": 0 }, "inputs": { "a": "pow", "b": "fib" } },
+                { "id": "zero", "task": { "type": "divide", "numerator": 1, "denominator": 0 } },
+                { "id": "after_zero", "task": { "type": "multiply", "a": 0, "b": 3 }, "inputs": { "a": "zero" } },
+                { "id": "last", "task": { "type": "compute", "a": 0, "b": 1 }, "inputs": { "a": "after_zero" } }
+            ] }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bind() {
+        let task = TaskType::ModuloExponentiation { base: 2, exponent: 0, modulus: 1000 };
+        assert_eq!(
+            bind(&task, "exponent", &TaskOutput::BigInteger(55)),
+            Ok(TaskType::ModuloExponentiation { base: 2, exponent: 55, modulus: 1000 })
+        );
+        let compute = TaskType::Compute { a: 0, b: 0 };
+        assert!(bind(&compute, "a", &TaskOutput::BigInteger(u64::MAX)).is_err());
+        assert!(bind(&compute, "c", &TaskOutput::Integer(1)).is_err());
+        assert!(bind(&compute, "type", &TaskOutput::Integer(1)).is_err());
+        assert!(bind(&compute, "a", &TaskOutput::Bool(true)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_graphs() {
+        let cycle = r#"{ "tasks": [
+            { "id": "a", "task": { "type": "compute", "a": 0, "b": 0 }, "inputs": { "a": "b" } },
+            { "id": "b", "task": { "type": "compute", "a": 0, "b": 0 }, "inputs": { "a": "a" } },
+            { "id": "c", "task": { "type": "compute", "a": 0, "b": 0 } }
+        ] }"#;
+        let error = TaskGraph::parse_json(cycle).unwrap_err();
+        assert!(error.contains("cycle among: a, b."), "{}", error);
+
+        let unknown = r#"{ "tasks": [ { "id": "a", "task": { "type": "compute", "a": 0, "b": 0 }, "inputs": { "a": "x" } } ] }"#;
+        assert!(TaskGraph::parse_json(unknown).unwrap_err().contains("unknown task 'x'"));
+        let bad_param = r#"{ "tasks": [
+            { "id": "a", "task": { "type": "compute", "a": 0, "b": 0 } },
+            { "id": "b", "task": { "type": "fibonacci", "n": 0 }, "inputs": { "m": "a" } }
+        ] }"#;
+        assert!(TaskGraph::parse_json(bad_param).unwrap_err().contains("no parameter 'm'"));
+        let duplicate = r#"{ "tasks": [
+            { "id": "a", "task": { "type": "compute", "a": 0, "b": 0 } },
+            { "id": "a", "task": { "type": "compute", "a": 0, "b": 0 } }
+        ] }"#;
+        assert!(TaskGraph::parse_json(duplicate).is_err());
+    }
+
+    #[test]
+    fn test_execute_dag() {
+        let graph = sample_graph();
+        // Zero threads is clamped to one worker, like every other executor
+        for thread_count in [0, 1, 4] {
+            let result = execute_dag(&graph, thread_count, TaskSettings::DEFAULT);
+            assert_eq!(result.outputs[0], Ok(TaskOutput::BigInteger(89)));
+            // 2^89 mod 1000
+            assert_eq!(result.outputs[1], Ok(TaskOutput::BigInteger(112)));
+            assert_eq!(result.outputs[2], Ok(TaskOutput::Integer(112 + 89)));
+            assert_eq!(result.outputs[3], Err(TaskError::DivisionByZero));
+            // The failure reaches everything downstream without running it
+            assert_eq!(result.outputs[4], Err(TaskError::UpstreamFailed("'zero' failed: Division by zero.".into())));
+            assert!(matches!(&result.outputs[5], Err(TaskError::UpstreamFailed(reason)) if reason.starts_with("'after_zero'")));
+            assert_eq!(result.timings[5].attempts, 0);
+            assert_eq!(result.workers.iter().map(|worker| worker.tasks).sum::<usize>(), 6);
+        }
+    }
+
+    #[test]
+    fn test_ready_tasks_by_priority() {
+        let graph = TaskGraph::parse_json(
+            r#"{ "tasks": [
+                { "id": "first", "task": { "type": "compute", "a": 1, "b": 1 } },
+                { "id": "low", "task": { "type": "compute", "a": 2, "b": 2 }, "priority": "low" },
+                { "id": "urgent", "task": { "type": "compute", "a": 3, "b": 3 }, "priority": "high" }
+            ] }"#,
+        )
+        .unwrap();
+        let priorities = graph.priorities(&Priorities::EQUAL);
+        assert_eq!(priorities.of(1, &graph.nodes()[1].task), Priority::Low);
+        assert_eq!(priorities.of(0, &graph.nodes()[0].task), Priority::Normal);
+
+        // One worker runs every ready task in priority order, whatever order the file lists them in
+        let result = execute_dag(&graph, 1, TaskSettings::DEFAULT);
+        let start = |index: usize| result.timings[index].runs[0].start;
+        assert!(start(2) < start(0) && start(0) < start(1));
+    }
+
+    #[test]
+    fn test_each_parameter_bound_once() {
+        let node = |id: &str, inputs: Vec<(String, usize)>| Node {
+            id: id.into(),
+            task: TaskType::Compute { a: 0, b: 0 },
+            inputs,
+            priority: None,
+        };
+        let nodes = vec![node("x", Vec::new()), node("y", vec![("a".into(), 0), ("a".into(), 0)])];
+        assert!(TaskGraph::new(nodes).unwrap_err().contains("more than one input for 'a'"));
+        let self_loop = vec![node("x", vec![("a".into(), 0)])];
+        assert!(TaskGraph::new(self_loop).unwrap_err().contains("cycle among: x."));
+    }
+}
@@ -0,0 +1,1069 @@
+use crate::cancel::CancellationToken;
+use crate::load::LoadModel;
+use crate::pool::ThreadPool;
+use crate::priority::{MultiLevelQueue, Priorities};
+use crate::retry::RetryPolicy;
+use crate::task::{Task, TaskError, TaskOutput, TaskType};
+use crossbeam::channel;
+use crossbeam::deque::{Injector, Stealer, Worker};
+use std::collections::{BTreeMap, VecDeque};
+use std::any::Any;
+use std::cell::Cell;
+use std::iter;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Once, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The outcome of executing a batch of tasks.
+pub struct ExecutionResult {
+    /// Total elapsed time to run the whole batch.
+    pub duration: Duration,
+    /// One entry per input task, in the same order as the input batch.
+    pub outputs: Vec<Result<TaskOutput, TaskError>>,
+    /// How long each task waited and ran, in the same order as `outputs`.
+    pub timings: Vec<TaskTiming>,
+    /// What each worker thread did, for executors that report it; empty otherwise.
+    pub workers: Vec<WorkerStats>,
+}
+
+/// Where the time went for a single task.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaskTiming {
+    /// Time from the task becoming available to a worker until a worker started it.
+    pub wait: Duration,
+    /// Time spent running the task, including any simulated load, summed over all attempts.
+    pub run: Duration,
+    /// Which worker finished the task, for executors that record it.
+    pub worker: Option<usize>,
+    /// How many times the task was run; more than one if the retry policy retried it.
+    pub attempts: u32,
+    /// Each attempt, in the order they were made; empty if the task never ran.
+    pub runs: Vec<AttemptTiming>,
+}
+
+/// When and where one attempt at a task ran.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AttemptTiming {
+    /// When the attempt started, counted from the start of the batch.
+    pub start: Duration,
+    /// Time spent running the attempt, including any simulated load.
+    pub run: Duration,
+    /// Which worker made the attempt, for executors that record it.
+    pub worker: Option<usize>,
+}
+
+impl TaskTiming {
+    /// Records that `worker` made every attempt not yet attributed to a worker, and that
+    /// it is the worker the task has run on so far.
+    pub fn ran_on(&mut self, worker: usize) {
+        self.worker = Some(worker);
+        for attempt in self.runs.iter_mut().filter(|attempt| attempt.worker.is_none()) {
+            attempt.worker = Some(worker);
+        }
+    }
+}
+
+/// How one worker thread spent a batch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Number of tasks the worker ran.
+    pub tasks: usize,
+    /// Number of those tasks that returned an error.
+    pub errors: usize,
+    /// Time spent running tasks.
+    pub busy: Duration,
+    /// How the worker fared on the shared queue's lock.
+    pub lock: LockStats,
+    /// The rest of the batch's wall time: starting up, and waiting for slower workers to finish.
+    pub idle: Duration,
+}
+
+/// Contention on a shared lock, as seen by one or more workers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockStats {
+    /// Number of times the lock was taken.
+    pub acquisitions: usize,
+    /// Acquisitions where `try_lock` found the lock already held and the worker had to block.
+    pub contended: usize,
+    /// Time spent waiting to acquire the lock.
+    pub wait: Duration,
+    /// Time spent holding the lock.
+    pub hold: Duration,
+}
+
+impl LockStats {
+    /// Fraction of acquisitions that had to block.
+    pub fn contention_rate(&self) -> f64 {
+        if self.acquisitions == 0 {
+            return 0.0;
+        }
+        self.contended as f64 / self.acquisitions as f64
+    }
+
+    /// Adds another worker's counts and times to these.
+    pub fn merge(&mut self, other: &LockStats) {
+        self.acquisitions += other.acquisitions;
+        self.contended += other.contended;
+        self.wait += other.wait;
+        self.hold += other.hold;
+    }
+}
+
+impl WorkerStats {
+    /// Fraction of `duration` the worker spent running tasks.
+    pub fn utilization(&self, duration: Duration) -> f64 {
+        if duration.is_zero() {
+            return 0.0;
+        }
+        self.busy.as_secs_f64() / duration.as_secs_f64()
+    }
+}
+
+/// A task's input index, its output and its timing, as produced by a worker.
+pub type TaskRecord = (usize, Result<TaskOutput, TaskError>, TaskTiming);
+
+impl ExecutionResult {
+    /// Builds a result from records produced in any order, restoring input order.
+    pub fn from_records(duration: Duration, mut records: Vec<TaskRecord>) -> ExecutionResult {
+        records.sort_by_key(|(index, _, _)| *index);
+        let (outputs, timings) = records.into_iter().map(|(_, output, timing)| (output, timing)).unzip();
+        ExecutionResult { duration, outputs, timings, workers: Vec::new() }
+    }
+
+    /// Lock statistics summed over every worker.
+    pub fn lock_totals(&self) -> LockStats {
+        let mut totals = LockStats::default();
+        for worker in &self.workers {
+            totals.merge(&worker.lock);
+        }
+        totals
+    }
+
+    /// How unevenly the work was spread: the busiest worker's busy time over the mean.
+    ///
+    /// # Returns
+    /// `1.0` for a perfectly balanced run, up to the worker count when one worker did
+    /// everything; `None` if no worker stats were recorded or no time was spent busy.
+    pub fn imbalance(&self) -> Option<f64> {
+        let busiest = self.workers.iter().map(|worker| worker.busy).max()?;
+        let total: Duration = self.workers.iter().map(|worker| worker.busy).sum();
+        if total.is_zero() {
+            return None;
+        }
+        Some(busiest.as_secs_f64() * self.workers.len() as f64 / total.as_secs_f64())
+    }
+
+    /// Counts failed tasks grouped by `TaskError::kind`.
+    pub fn failures_by_kind(&self) -> BTreeMap<&'static str, usize> {
+        let mut failures = BTreeMap::new();
+        for error in self.outputs.iter().filter_map(|result| result.as_ref().err()) {
+            *failures.entry(error.kind()).or_insert(0) += 1;
+        }
+        failures
+    }
+
+    /// Number of retries over the whole batch, and how many tasks succeeded after retrying.
+    pub fn retries(&self) -> (usize, usize) {
+        let retried = self.timings.iter().zip(&self.outputs).filter(|(timing, _)| timing.attempts > 1);
+        retried.fold((0, 0), |(retries, recovered), (timing, output)| {
+            (retries + timing.attempts as usize - 1, recovered + output.is_ok() as usize)
+        })
+    }
+
+    /// The index and payload message of every task that panicked, in input order.
+    pub fn panics(&self) -> Vec<(usize, &str)> {
+        self.outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, output)| match output {
+                Err(TaskError::Panicked(message)) => Some((index, message.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Counts task outputs that differ from `expected`'s, plus any difference in length.
+    ///
+    /// Tasks interrupted by a timeout or cancellation, or failed transiently, in either run are
+    /// not compared, since those outcomes depend on timing or chance rather than on the
+    /// executor being correct.
+    pub fn mismatches(&self, expected: &ExecutionResult) -> usize {
+        let interrupted = |output: &Result<TaskOutput, TaskError>| {
+            output.as_ref().is_err_and(|e| e.is_interrupted() || e.is_transient())
+        };
+        expected
+            .outputs
+            .iter()
+            .zip(&self.outputs)
+            .filter(|(a, b)| a != b && !interrupted(a) && !interrupted(b))
+            .count()
+            + expected.outputs.len().abs_diff(self.outputs.len())
+    }
+}
+
+/// How every task in a batch is run, whichever executor runs it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskSettings {
+    /// Simulated latency added inside every task.
+    pub load: LoadModel,
+    /// Deadline for each task, counted from when it starts.
+    pub timeout: Option<Duration>,
+    /// Deadline for the whole batch, counted from when the executor starts. Running tasks
+    /// time out when it passes and tasks that have not started yet are cancelled.
+    pub batch_timeout: Option<Duration>,
+    /// Which failed tasks are attempted again, and after how long.
+    pub retry: RetryPolicy,
+    /// The priority of each task kind, and of tasks that set their own, used by
+    /// `Executor::Priority` and `execute_dag`.
+    pub priorities: Priorities,
+}
+
+impl TaskSettings {
+    /// No simulated load and no deadlines.
+    pub const DEFAULT: TaskSettings = TaskSettings {
+        load: LoadModel::NONE,
+        timeout: None,
+        batch_timeout: None,
+        retry: RetryPolicy::NONE,
+        priorities: Priorities::EQUAL,
+    };
+}
+
+/// Number of tasks the atomic-index executor claims at once unless told otherwise.
+pub const DEFAULT_CHUNK_SIZE: usize = 1;
+
+/// How long a task waits in the priority executor's queue before it is aged up a level,
+/// unless told otherwise.
+pub const DEFAULT_AGING: Duration = Duration::from_millis(10);
+
+/// The concurrent execution strategies that can be selected from the CLI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Executor {
+    /// Every worker pops from one shared `Mutex<VecDeque>`.
+    MutexQueue,
+    /// Per-worker crossbeam deques fed by a global injector, with stealing between workers.
+    WorkStealing,
+    /// A producer streams tasks into a bounded crossbeam MPMC channel that workers receive from.
+    Channel,
+    /// Workers claim `chunk_size` consecutive tasks at a time from a shared atomic cursor.
+    AtomicIndex { chunk_size: usize },
+    /// Tasks are submitted to a persistent `ThreadPool` whose workers outlive the batch.
+    Pool,
+    /// Workers pop the most urgent task from a mutex-guarded `MultiLevelQueue`, which ages
+    /// waiting tasks up a level every `aging`.
+    Priority { aging: Duration },
+}
+
+impl Executor {
+    /// All executors, in the order they are offered and benchmarked.
+    pub const ALL: [Executor; 6] = [
+        Executor::MutexQueue,
+        Executor::WorkStealing,
+        Executor::Channel,
+        Executor::AtomicIndex { chunk_size: DEFAULT_CHUNK_SIZE },
+        Executor::Pool,
+        Executor::Priority { aging: DEFAULT_AGING },
+    ];
+
+    /// Human-readable name used in prompts and summaries.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Executor::MutexQueue => "Mutex queue",
+            Executor::WorkStealing => "Work-stealing",
+            Executor::Channel => "Channel",
+            Executor::AtomicIndex { .. } => "Atomic index",
+            Executor::Pool => "Thread pool",
+            Executor::Priority { .. } => "Priority queue",
+        }
+    }
+
+    /// Short identifier used to select the executor on the command line.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Executor::MutexQueue => "mutex",
+            Executor::WorkStealing => "work-stealing",
+            Executor::Channel => "channel",
+            Executor::AtomicIndex { .. } => "atomic",
+            Executor::Pool => "pool",
+            Executor::Priority { .. } => "priority",
+        }
+    }
+
+    /// Runs `tasks` on `thread_count` workers (at least 1) using this strategy.
+    ///
+    /// `Executor::Pool` builds a pool for this call only; callers that run several batches
+    /// should keep their own `ThreadPool` and call `ThreadPool::execute` on it instead.
+    pub fn execute(&self, tasks: &[TaskType], thread_count: u32, settings: TaskSettings) -> ExecutionResult {
+        match self {
+            Executor::MutexQueue => execute_concurrently(tasks, thread_count, settings),
+            Executor::WorkStealing => execute_work_stealing(tasks, thread_count, settings),
+            Executor::Channel => execute_channel(tasks, thread_count, settings),
+            Executor::AtomicIndex { chunk_size } => {
+                execute_atomic_index(tasks, thread_count, settings, *chunk_size)
+            }
+            Executor::Pool => ThreadPool::new(thread_count, settings).execute(tasks),
+            Executor::Priority { aging } => execute_priority(tasks, thread_count, settings, *aging),
+        }
+    }
+}
+
+/// Runs a single task with the given simulated load and deadlines.
+///
+/// Shared by every executor so that they all do exactly the same work per task. A task that
+/// panics is recorded as `TaskError::Panicked` instead of unwinding through the worker.
+/// Errors are only returned, never printed; `summary::failures` reports them per batch.
+///
+/// # Arguments
+/// * `task` - The task to run.
+/// * `settings` - Simulated load and per-task timeout.
+/// * `batch` - The batch's token; once its deadline passes it is cancelled, so every task
+///   that has not started yet is skipped with `TaskError::Cancelled`.
+pub fn run_task<T: Task + ?Sized>(
+    task: &T,
+    settings: &TaskSettings,
+    batch: &CancellationToken,
+) -> Result<TaskOutput, TaskError> {
+    match batch.check() {
+        Ok(()) => run_isolated(task, settings, &batch.child(settings.timeout)),
+        Err(TaskError::Timeout) => {
+            batch.cancel();
+            Err(TaskError::Cancelled)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+thread_local! {
+    /// Set while `run_isolated` runs a task on this thread, so that the panic hook stays quiet.
+    static ISOLATING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs a task, turning a panic inside it into `TaskError::Panicked` so that the worker
+/// running it survives and carries on with the rest of the batch.
+///
+/// The panic is still printed by the panic hook unless `install_quiet_panic_hook` was
+/// called, in which case it is reported once, through the task's result.
+fn run_isolated<T: Task + ?Sized>(
+    task: &T,
+    settings: &TaskSettings,
+    cancel: &CancellationToken,
+) -> Result<TaskOutput, TaskError> {
+    ISOLATING.set(true);
+    // Tasks only borrow their inputs immutably, so nothing is left half-updated by a panic
+    let result = panic::catch_unwind(AssertUnwindSafe(|| task.run(&settings.load, cancel)));
+    ISOLATING.set(false);
+    result.unwrap_or_else(|payload| Err(TaskError::Panicked(panic_message(payload.as_ref()))))
+}
+
+/// Installs a panic hook that prints nothing for task panics, which workers catch and
+/// return as `TaskError::Panicked`, and hands every other panic to the hook that was
+/// installed before.
+///
+/// The panic hook is process-wide, so the library never installs this by itself; call it
+/// once at start-up to keep caught task panics from also being printed with a backtrace
+/// hint. Later calls do nothing.
+pub fn install_quiet_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !ISOLATING.get() {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// The message a panic was raised with, if it was a string.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+/// Runs a task with `run_task` and records how long it waited and ran.
+///
+/// # Arguments
+/// * `task` - The task to run.
+/// * `settings` - Simulated load and timeout applied to the task.
+/// * `batch` - The batch's cancellation token.
+/// * `queued_at` - When the task became available to workers.
+fn run_timed<T: Task + ?Sized>(
+    task: &T,
+    settings: &TaskSettings,
+    batch: &CancellationToken,
+    queued_at: Instant,
+) -> (Result<TaskOutput, TaskError>, TaskTiming) {
+    let started = Instant::now();
+    let result = run_task(task, settings, batch);
+    let run = started.elapsed();
+    let attempt = AttemptTiming { start: started.saturating_duration_since(batch.started()), run, worker: None };
+    let timing = TaskTiming { wait: started - queued_at, run, worker: None, attempts: 1, runs: vec![attempt] };
+    (result, timing)
+}
+
+/// A failed task waiting to be attempted again.
+#[derive(Clone, Debug)]
+pub struct Retry {
+    /// The task's timing over the attempts made so far.
+    pub timing: TaskTiming,
+    /// When the retry policy's backoff is over.
+    pub ready_at: Instant,
+}
+
+/// The result of one attempt at a task.
+pub enum Attempt {
+    /// The task succeeded, or failed in a way the retry policy does not retry.
+    Finished(Result<TaskOutput, TaskError>, TaskTiming),
+    /// The task failed and should be requeued to be attempted again.
+    Requeue(Retry),
+}
+
+/// Makes one attempt at a task, first waiting out the backoff if it is being retried.
+///
+/// Executors with a queue requeue the `Attempt::Requeue` it returns, so that other tasks run
+/// during the backoff; the others retry in place with `run_with_retries`.
+///
+/// # Arguments
+/// * `task` - The task to run.
+/// * `settings` - Simulated load, timeout and retry policy applied to the task.
+/// * `batch` - The batch's cancellation token; a cancelled batch is never retried.
+/// * `queued_at` - When the task first became available to workers.
+/// * `retry` - The task's earlier attempts, if this is a retry.
+pub fn run_attempt<T: Task + ?Sized>(
+    task: &T,
+    settings: &TaskSettings,
+    batch: &CancellationToken,
+    queued_at: Instant,
+    retry: Option<Retry>,
+) -> Attempt {
+    if let Some(Retry { ready_at, .. }) = retry {
+        let backoff = ready_at.saturating_duration_since(Instant::now());
+        thread::sleep(batch.remaining().map_or(backoff, |remaining| remaining.min(backoff)));
+    }
+    let (result, mut timing) = run_timed(task, settings, batch, queued_at);
+    if let Some(Retry { timing: mut earlier, .. }) = retry {
+        earlier.runs.append(&mut timing.runs);
+        timing = TaskTiming {
+            wait: earlier.wait,
+            run: earlier.run + timing.run,
+            attempts: earlier.attempts + 1,
+            runs: earlier.runs,
+            ..timing
+        };
+    }
+    match &result {
+        Err(e) if settings.retry.should_retry(e, timing.attempts) && batch.check().is_ok() => {
+            let ready_at = Instant::now() + settings.retry.backoff(timing.attempts);
+            Attempt::Requeue(Retry { timing, ready_at })
+        }
+        _ => Attempt::Finished(result, timing),
+    }
+}
+
+/// Runs a task until it finishes or the retry policy gives up, backing off in place.
+///
+/// # Returns
+/// The task's final result and its timing over all attempts.
+pub fn run_with_retries<T: Task + ?Sized>(
+    task: &T,
+    settings: &TaskSettings,
+    batch: &CancellationToken,
+    queued_at: Instant,
+) -> (Result<TaskOutput, TaskError>, TaskTiming) {
+    let mut retry = None;
+    loop {
+        match run_attempt(task, settings, batch, queued_at, retry) {
+            Attempt::Finished(result, timing) => return (result, timing),
+            Attempt::Requeue(next) => retry = Some(next),
+        }
+    }
+}
+
+/// Executes a list of tasks one at a time in serial order,
+/// measuring the total time taken to complete all tasks.
+///
+/// # Arguments
+/// * `tasks` - A slice of `TaskType` values to be executed.
+/// * `settings` - Simulated load and timeouts applied to each task.
+///
+/// # Returns
+/// An `ExecutionResult` with the total elapsed time and the output of every task.
+pub fn execute_serially(tasks: &[TaskType], settings: TaskSettings) -> ExecutionResult {
+    let mut records = Vec::with_capacity(tasks.len());
+    let start = Instant::now();
+    let batch = CancellationToken::with_timeout(settings.batch_timeout);
+    let mut queue: VecDeque<(usize, Option<Retry>)> = (0..tasks.len()).map(|index| (index, None)).collect();
+    while let Some((index, retry)) = queue.pop_front() {
+        // Every task is queued from the start, so later tasks wait for earlier ones
+        match run_attempt(&tasks[index], &settings, &batch, start, retry) {
+            Attempt::Finished(result, mut timing) => {
+                timing.ran_on(0);
+                records.push((index, result, timing));
+            }
+            // Retries go to the back of the queue, behind every task not yet attempted
+            Attempt::Requeue(retry) => queue.push_back((index, Some(retry))),
+        }
+    }
+    ExecutionResult::from_records(start.elapsed(), records)
+}
+
+/// Executes a list of tasks concurrently using multiple threads and returns the total duration.
+///
+/// Tasks are distributed among threads by having each thread pop from a shared task queue
+/// protected by a mutex. Threads continue pulling tasks until the queue is empty.
+/// Each worker keeps the outputs it produced alongside the task's original index,
+/// and the outputs are merged back into input order once all workers have joined.
+/// Each worker also reports a `WorkerStats` with its task count, errors, busy time and
+/// how often and how long it waited for and held the queue's lock.
+///
+/// # Arguments
+/// * `tasks` - A slice of `TaskType` elements to be executed.
+/// * `thread_count` - Number of threads to spawn for concurrent execution (at least 1).
+/// * `settings` - Simulated load and timeouts applied to each task.
+///
+/// # Returns
+/// An `ExecutionResult` with the total elapsed time and the output of every task.
+pub fn execute_concurrently(tasks: &[TaskType], thread_count: u32, settings: TaskSettings) -> ExecutionResult {
+    let thread_count = thread_count.max(1);
+
+    // Wrap the task queue in Arc<Mutex<...>> to allow shared, synchronized access across threads.
+    // Tasks are paired with their index so outputs can be put back in input order.
+    // Failed tasks are requeued at the back along with their earlier attempts.
+    let indexed: VecDeque<(usize, TaskType, Option<Retry>)> =
+        tasks.iter().cloned().enumerate().map(|(index, task)| (index, task, None)).collect();
+    let queue = Arc::new(Mutex::new(indexed));
+    let mut handles = Vec::new();
+
+    let start_time = Instant::now();
+    let batch = CancellationToken::with_timeout(settings.batch_timeout);
+
+    // Launch the specified number of worker threads
+    for worker in 0..thread_count as usize {
+        let task_queue = Arc::clone(&queue);
+        let batch = batch.clone();
+        let settings = settings.clone();
+
+        let handle = thread::spawn(move || {
+            let mut produced = Vec::new();
+            let mut stats = WorkerStats::default();
+            loop {
+                 // Lock the queue and try to pop the next task
+                let maybe_task = with_lock(&task_queue, &mut stats.lock, VecDeque::pop_front);
+
+                match maybe_task {
+                    // Execute the task and keep its output
+                    Some((index, task, retry)) => {
+                        let earlier_run = retry.as_ref().map_or(Duration::ZERO, |retry| retry.timing.run);
+                        match run_attempt(&task, &settings, &batch, start_time, retry) {
+                            Attempt::Finished(result, mut timing) => {
+                                timing.ran_on(worker);
+                                stats.tasks += 1;
+                                stats.errors += result.is_err() as usize;
+                                stats.busy += timing.run - earlier_run;
+                                produced.push((index, result, timing));
+                            }
+                            Attempt::Requeue(mut retry) => {
+                                retry.timing.ran_on(worker);
+                                stats.busy += retry.timing.run - earlier_run;
+                                with_lock(&task_queue, &mut stats.lock, |queue| queue.push_back((index, task, Some(retry))));
+                            }
+                        }
+                    }
+                    None => break, // Exit the loop if the queue is empty
+                }
+            }
+            (produced, stats)
+        });
+        // Store the handle so we can join it later
+        handles.push(handle);
+    }
+
+    // Wait for all threads to finish and gather what each of them produced
+    let mut produced = Vec::with_capacity(tasks.len());
+    let mut workers = Vec::with_capacity(handles.len());
+    for handle in handles {
+        let (records, stats) = handle.join().expect("Thread panicked during execution");
+        produced.extend(records);
+        workers.push(stats);
+    }
+    let duration = Instant::now() - start_time;
+
+    // Whatever part of the batch a worker was neither running tasks nor on the lock
+    for stats in &mut workers {
+        stats.idle = duration.saturating_sub(stats.busy + stats.lock.wait + stats.lock.hold);
+    }
+    let mut result = ExecutionResult::from_records(duration, produced);
+    result.workers = workers;
+    result
+}
+
+/// Executes a list of tasks in priority order using multiple threads.
+///
+/// Every task is pushed onto a `MultiLevelQueue` at the level `settings.priorities` gives its
+/// kind. Workers pop the most urgent task under a mutex, so high-priority tasks start first,
+/// while aging lifts tasks that have waited `aging` or more so that low-priority tasks are
+/// not starved. Failed tasks the retry policy retries are pushed back at their own level.
+/// Workers report the same `WorkerStats` as `execute_concurrently`.
+///
+/// # Arguments
+/// * `tasks` - A slice of `TaskType` elements to be executed.
+/// * `thread_count` - Number of worker threads to spawn (at least 1).
+/// * `settings` - Simulated load, timeouts, retry policy and task priorities.
+/// * `aging` - How long a task waits before it is treated as one level more urgent.
+///
+/// # Returns
+/// An `ExecutionResult` with the total elapsed time and the output of every task.
+pub fn execute_priority(tasks: &[TaskType], thread_count: u32, settings: TaskSettings, aging: Duration) -> ExecutionResult {
+    let thread_count = thread_count.max(1);
+    let start_time = Instant::now();
+    let batch = CancellationToken::with_timeout(settings.batch_timeout);
+
+    // Tasks are shared by reference, so the queue only holds indices and earlier attempts
+    let mut levels = MultiLevelQueue::new(aging);
+    for (index, task) in tasks.iter().enumerate() {
+        levels.push((index, None), settings.priorities.of(index, task));
+    }
+    let queue: Mutex<MultiLevelQueue<(usize, Option<Retry>)>> = Mutex::new(levels);
+
+    let (produced, mut workers): (Vec<_>, Vec<_>) = thread::scope(|scope| {
+        let handles: Vec<_> = (0..thread_count as usize)
+            .map(|worker| {
+                let (queue, batch, settings) = (&queue, &batch, &settings);
+                scope.spawn(move || {
+                    let mut produced = Vec::new();
+                    let mut stats = WorkerStats::default();
+                    while let Some(((index, retry), priority)) = with_lock(queue, &mut stats.lock, MultiLevelQueue::pop) {
+                        let earlier_run = retry.as_ref().map_or(Duration::ZERO, |retry| retry.timing.run);
+                        match run_attempt(&tasks[index], settings, batch, start_time, retry) {
+                            Attempt::Finished(result, mut timing) => {
+                                timing.ran_on(worker);
+                                stats.tasks += 1;
+                                stats.errors += result.is_err() as usize;
+                                stats.busy += timing.run - earlier_run;
+                                produced.push((index, result, timing));
+                            }
+                            Attempt::Requeue(mut retry) => {
+                                retry.timing.ran_on(worker);
+                                stats.busy += retry.timing.run - earlier_run;
+                                with_lock(queue, &mut stats.lock, |queue| queue.push((index, Some(retry)), priority));
+                            }
+                        }
+                    }
+                    (produced, stats)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().expect("Thread panicked during execution")).unzip()
+    });
+    let duration = start_time.elapsed();
+
+    for stats in &mut workers {
+        stats.idle = duration.saturating_sub(stats.busy + stats.lock.wait + stats.lock.hold);
+    }
+    let mut result = ExecutionResult::from_records(duration, produced.into_iter().flatten().collect());
+    result.workers = workers;
+    result
+}
+
+/// Runs `f` on the value behind `mutex`, recording in `lock` how long it took to acquire
+/// the lock, whether it was contended, and how long it was held.
+fn with_lock<T, R>(mutex: &Mutex<T>, lock: &mut LockStats, f: impl FnOnce(&mut T) -> R) -> R {
+    let (mut guard, acquired) = lock_timed(mutex, lock);
+    let result = f(&mut guard);
+    drop(guard);
+    lock.hold += acquired.elapsed();
+    result
+}
+
+/// Locks `mutex`, recording in `lock` the acquisition, how long it took and whether it was
+/// contended. The caller adds the hold time, counted from the returned `Instant`, once it
+/// releases the guard.
+pub fn lock_timed<'a, T>(mutex: &'a Mutex<T>, lock: &mut LockStats) -> (MutexGuard<'a, T>, Instant) {
+    let requested = Instant::now();
+    // A failed try_lock means another worker holds the lock, so this acquisition is contended
+    let guard = match mutex.try_lock() {
+        Ok(guard) => guard,
+        Err(TryLockError::WouldBlock) => {
+            lock.contended += 1;
+            mutex.lock().unwrap()
+        }
+        Err(TryLockError::Poisoned(_)) => mutex.lock().unwrap(),
+    };
+    let acquired = Instant::now();
+    lock.acquisitions += 1;
+    lock.wait += acquired - requested;
+    (guard, acquired)
+}
+
+/// Executes a list of tasks on a work-stealing scheduler built on `crossbeam::deque`.
+///
+/// All tasks start in a global `Injector`. Each worker owns a local FIFO deque: it first
+/// pops from its own deque, then refills it with a batch from the injector, and only when
+/// both are empty does it steal from the other workers. A worker exits once every source
+/// reports empty, since no new tasks are created during the run.
+///
+/// # Arguments
+/// * `tasks` - A slice of `TaskType` elements to be executed.
+/// * `thread_count` - Number of worker threads to spawn (at least 1).
+/// * `settings` - Simulated load and timeouts applied to each task.
+///
+/// # Returns
+/// An `ExecutionResult` with the total elapsed time and the output of every task.
+pub fn execute_work_stealing(tasks: &[TaskType], thread_count: u32, settings: TaskSettings) -> ExecutionResult {
+    let thread_count = thread_count.max(1);
+    let start_time = Instant::now();
+    let batch = CancellationToken::with_timeout(settings.batch_timeout);
+
+    // Tasks are shared by reference, so nothing has to be cloned up front
+    let injector = Injector::new();
+    for indexed in tasks.iter().enumerate() {
+        injector.push(indexed);
+    }
+
+    let workers: Vec<Worker<(usize, &TaskType)>> = (0..thread_count).map(|_| Worker::new_fifo()).collect();
+    let stealers: Vec<Stealer<(usize, &TaskType)>> = workers.iter().map(Worker::stealer).collect();
+
+    let produced = thread::scope(|scope| {
+        let handles: Vec<_> = workers
+            .into_iter()
+            .map(|local| {
+                let (injector, stealers, batch, settings) = (&injector, &stealers, &batch, &settings);
+                scope.spawn(move || {
+                    let mut produced = Vec::new();
+                    while let Some((index, task)) = find_task(&local, injector, stealers) {
+                        let (result, timing) = run_with_retries(task, settings, batch, start_time);
+                        produced.push((index, result, timing));
+                    }
+                    produced
+                })
+            })
+            .collect();
+
+        let mut produced = Vec::with_capacity(tasks.len());
+        for handle in handles {
+            produced.extend(handle.join().expect("Thread panicked during execution"));
+        }
+        produced
+    });
+    let duration = start_time.elapsed();
+
+    ExecutionResult::from_records(duration, produced)
+}
+
+/// Finds the next task for a work-stealing worker.
+///
+/// Tries the worker's own deque first, then a batch from the global injector,
+/// then the other workers' deques. Returns `None` once every source is empty.
+fn find_task<T>(local: &Worker<T>, injector: &Injector<T>, stealers: &[Stealer<T>]) -> Option<T> {
+    local.pop().or_else(|| {
+        // Keep trying while a steal operation asks to be retried
+        iter::repeat_with(|| {
+            injector
+                .steal_batch_and_pop(local)
+                .or_else(|| stealers.iter().map(Stealer::steal).collect())
+        })
+        .find(|steal| !steal.is_retry())
+        .and_then(|steal| steal.success())
+    })
+}
+
+/// Number of in-flight tasks the channel executor buffers per worker.
+const CHANNEL_CAPACITY_PER_WORKER: usize = 64;
+
+/// Executes a list of tasks by streaming them through a bounded `crossbeam::channel`.
+///
+/// A dedicated producer thread sends tasks into the channel while `thread_count` workers
+/// receive from it, so workers start executing before the whole batch has been queued.
+/// Once the producer has sent every task it drops its sender, which disconnects the
+/// channel and lets each worker exit after draining what is left.
+///
+/// # Arguments
+/// * `tasks` - A slice of `TaskType` elements to be executed.
+/// * `thread_count` - Number of worker threads to spawn (at least 1).
+/// * `settings` - Simulated load and timeouts applied to each task.
+///
+/// # Returns
+/// An `ExecutionResult` with the total elapsed time and the output of every task.
+pub fn execute_channel(tasks: &[TaskType], thread_count: u32, settings: TaskSettings) -> ExecutionResult {
+    let thread_count = thread_count.max(1);
+    let start_time = Instant::now();
+    let batch = CancellationToken::with_timeout(settings.batch_timeout);
+
+    let capacity = thread_count as usize * CHANNEL_CAPACITY_PER_WORKER;
+    // Each message carries the instant it was sent, which is when the task was queued
+    let (sender, receiver) = channel::bounded::<(usize, &TaskType, Instant)>(capacity);
+
+    let produced = thread::scope(|scope| {
+        // Producer: blocks whenever the channel is full, so memory use stays bounded
+        scope.spawn(move || {
+            for (index, task) in tasks.iter().enumerate() {
+                sender.send((index, task, Instant::now())).expect("All channel workers exited early");
+            }
+        });
+
+        let handles: Vec<_> = (0..thread_count)
+            .map(|_| {
+                let (receiver, batch, settings) = (receiver.clone(), &batch, &settings);
+                scope.spawn(move || {
+                    // `iter` ends once the producer is done and the channel is empty
+                    receiver
+                        .iter()
+                        .map(|(index, task, queued_at)| {
+                            let (result, timing) = run_with_retries(task, settings, batch, queued_at);
+                            (index, result, timing)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        drop(receiver);
+
+        let mut produced = Vec::with_capacity(tasks.len());
+        for handle in handles {
+            produced.extend(handle.join().expect("Thread panicked during execution"));
+        }
+        produced
+    });
+    let duration = start_time.elapsed();
+
+    ExecutionResult::from_records(duration, produced)
+}
+
+/// Executes a list of tasks by handing out slice indices from a shared `AtomicUsize` cursor.
+///
+/// Scoped threads borrow `tasks` directly, so the batch is never cloned and no lock is taken.
+/// Each worker claims the next `chunk_size` indices with a single `fetch_add` and runs them
+/// in order; larger chunks mean fewer atomic operations at the cost of coarser load balancing.
+///
+/// # Arguments
+/// * `tasks` - A slice of `TaskType` elements to be executed.
+/// * `thread_count` - Number of worker threads to spawn (at least 1).
+/// * `settings` - Simulated load and timeouts applied to each task.
+/// * `chunk_size` - Number of consecutive tasks claimed per atomic operation (at least 1).
+///
+/// # Returns
+/// An `ExecutionResult` with the total elapsed time and the output of every task.
+pub fn execute_atomic_index(
+    tasks: &[TaskType],
+    thread_count: u32,
+    settings: TaskSettings,
+    chunk_size: usize,
+) -> ExecutionResult {
+    let thread_count = thread_count.max(1);
+    let chunk_size = chunk_size.max(1);
+    let cursor = AtomicUsize::new(0);
+    let start_time = Instant::now();
+    let batch = CancellationToken::with_timeout(settings.batch_timeout);
+
+    let produced = thread::scope(|scope| {
+        let handles: Vec<_> = (0..thread_count)
+            .map(|_| {
+                let (cursor, batch, settings) = (&cursor, &batch, &settings);
+                scope.spawn(move || {
+                    let mut produced = Vec::new();
+                    loop {
+                        // Only the index needs to be unique, so relaxed ordering is enough
+                        let begin = cursor.fetch_add(chunk_size, Ordering::Relaxed);
+                        if begin >= tasks.len() {
+                            break;
+                        }
+                        let end = (begin + chunk_size).min(tasks.len());
+                        for (index, task) in tasks.iter().enumerate().take(end).skip(begin) {
+                            let (result, timing) = run_with_retries(task, settings, batch, start_time);
+                            produced.push((index, result, timing));
+                        }
+                    }
+                    produced
+                })
+            })
+            .collect();
+
+        let mut produced = Vec::with_capacity(tasks.len());
+        for handle in handles {
+            produced.extend(handle.join().expect("Thread panicked during execution"));
+        }
+        produced
+    });
+    let duration = start_time.elapsed();
+
+    ExecutionResult::from_records(duration, produced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tasks() -> Vec<TaskType> {
+        (0..200)
+            .map(|i| match i % 4 {
+                0 => TaskType::Compute { a: i, b: i },
+                1 => TaskType::Divide { numerator: i, denominator: i % 3 },
+                2 => TaskType::PrimeCheck { n: i as u32 },
+                _ => TaskType::Fibonacci { n: (i % 40) as u32 },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_executors_match_serial() {
+        let tasks = sample_tasks();
+        let serial = execute_serially(&tasks, TaskSettings::DEFAULT);
+        for executor in Executor::ALL {
+            let result = executor.execute(&tasks, 4, TaskSettings::DEFAULT);
+            assert_eq!(result.outputs, serial.outputs, "{} diverged", executor.name());
+        }
+    }
+
+    #[test]
+    fn test_atomic_index_chunks() {
+        let tasks = sample_tasks();
+        let serial = execute_serially(&tasks, TaskSettings::DEFAULT);
+        // Chunk sizes that divide the batch evenly, leave a remainder, and exceed it
+        for chunk_size in [1, 7, 50, 1000] {
+            let result = execute_atomic_index(&tasks, 3, TaskSettings::DEFAULT, chunk_size);
+            assert_eq!(result.outputs, serial.outputs, "chunk size {} diverged", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_failures_by_kind() {
+        let result = execute_serially(&sample_tasks(), TaskSettings::DEFAULT);
+        let failures = result.failures_by_kind();
+        assert_eq!(failures.get("division by zero"), Some(&16));
+        assert_eq!(failures.len(), 1);
+    }
+
+    #[test]
+    fn test_mutex_queue_worker_stats() {
+        let tasks = sample_tasks();
+        let result = execute_concurrently(&tasks, 3, TaskSettings::DEFAULT);
+        assert_eq!(result.workers.len(), 3);
+        assert_eq!(result.workers.iter().map(|worker| worker.tasks).sum::<usize>(), tasks.len());
+        assert_eq!(result.workers.iter().map(|worker| worker.errors).sum::<usize>(), 16);
+        for worker in &result.workers {
+            // Every worker takes the lock once per task plus once to find the queue empty
+            assert_eq!(worker.lock.acquisitions, worker.tasks + 1);
+            assert!(worker.lock.contended <= worker.lock.acquisitions);
+            assert!(worker.busy + worker.lock.wait + worker.lock.hold + worker.idle <= result.duration);
+        }
+        assert_eq!(result.lock_totals().acquisitions, tasks.len() + 3);
+        assert!(execute_serially(&tasks, TaskSettings::DEFAULT).workers.is_empty());
+    }
+
+    #[test]
+    fn test_timeouts() {
+        // Trial division of a large prime takes far longer than a microsecond
+        let tasks = vec![TaskType::PrimeCheck { n: 4_294_967_291 }; 8];
+        let per_task = TaskSettings { timeout: Some(Duration::from_micros(1)), ..TaskSettings::DEFAULT };
+        for executor in Executor::ALL {
+            let result = executor.execute(&tasks, 2, per_task.clone());
+            assert_eq!(result.failures_by_kind().get("timeout"), Some(&8), "{} missed a timeout", executor.name());
+        }
+
+        // Once the batch deadline passes, tasks that have not started yet are cancelled
+        let slow = TaskSettings { load: LoadModel::parse("fixed:2ms").unwrap(), ..TaskSettings::DEFAULT };
+        let batch = TaskSettings { batch_timeout: Some(Duration::from_millis(5)), ..slow.clone() };
+        let tasks = vec![TaskType::Compute { a: 1, b: 1 }; 20];
+        let result = execute_serially(&tasks, batch);
+        let failures = result.failures_by_kind();
+        assert!(failures.get("cancelled").is_some_and(|&cancelled| cancelled >= 15));
+        assert!(failures.keys().all(|kind| ["cancelled", "timeout"].contains(kind)), "{:?}", failures);
+        assert_eq!(result.mismatches(&execute_serially(&tasks, slow)), 0);
+    }
+
+    #[test]
+    fn test_retries() {
+        let tasks = sample_tasks();
+        let serial = execute_serially(&tasks, TaskSettings::DEFAULT);
+
+        // Half of all attempts fail, but 30 attempts are all but certain to get through
+        let flaky = TaskSettings {
+            load: LoadModel::NONE.with_failure_rate(0.5),
+            retry: RetryPolicy::parse("30").unwrap(),
+            ..TaskSettings::DEFAULT
+        };
+        for result in iter::once(execute_serially(&tasks, flaky.clone())).chain(Executor::ALL.iter().map(|e| e.execute(&tasks, 3, flaky.clone()))) {
+            assert_eq!(result.outputs, serial.outputs);
+            let (retries, recovered) = result.retries();
+            assert!(retries > 0 && recovered > 0);
+            assert_eq!(retries, result.timings.iter().map(|timing| timing.attempts as usize - 1).sum::<usize>());
+        }
+
+        // Deterministic errors are never retried, and transient ones stop at the attempt limit
+        let failing = TaskSettings { load: LoadModel::NONE.with_failure_rate(1.0), ..flaky.clone() };
+        let failing = TaskSettings { retry: RetryPolicy::parse("3").unwrap(), ..failing };
+        let result = execute_concurrently(&tasks, 3, failing);
+        assert_eq!(result.failures_by_kind().get("transient"), Some(&tasks.len()));
+        assert!(result.timings.iter().all(|timing| timing.attempts == 3));
+        assert_eq!(result.workers.iter().map(|worker| worker.tasks).sum::<usize>(), tasks.len());
+        let reliable = TaskSettings { load: LoadModel::NONE, ..flaky };
+        let divide = execute_serially(&[TaskType::Divide { numerator: 1, denominator: 0 }], reliable);
+        assert_eq!(divide.timings[0].attempts, 1);
+    }
+
+    #[test]
+    fn test_zero_threads_run_on_one_worker() {
+        let tasks = sample_tasks();
+        let serial = execute_serially(&tasks, TaskSettings::DEFAULT);
+        for executor in Executor::ALL {
+            let result = executor.execute(&tasks, 0, TaskSettings::DEFAULT);
+            assert_eq!(result.outputs, serial.outputs, "{} lost tasks with 0 threads", executor.name());
+            // Not every executor reports worker stats, but none reports more than one worker
+            assert!(result.workers.len() <= 1, "{} ran more than one worker", executor.name());
+        }
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let tasks = sample_tasks();
+        let priorities = Priorities::parse("compute:high,fibonacci:low").unwrap();
+        let settings = TaskSettings { priorities, ..TaskSettings::DEFAULT };
+        let wait_of = |result: &ExecutionResult, key: &str| -> Vec<Duration> {
+            tasks.iter().zip(&result.timings).filter(|(task, _)| task.key() == key).map(|(_, timing)| timing.wait).collect()
+        };
+
+        // With one worker and no aging, every high-priority task starts before any other
+        let strict = execute_priority(&tasks, 1, settings.clone(), Duration::ZERO);
+        assert_eq!(strict.outputs, execute_serially(&tasks, settings.clone()).outputs);
+        let last_high = wait_of(&strict, "compute").into_iter().max().unwrap();
+        let first_normal = wait_of(&strict, "divide").into_iter().min().unwrap();
+        let first_low = wait_of(&strict, "fibonacci").into_iter().min().unwrap();
+        assert!(last_high <= first_normal && first_normal <= first_low);
+        assert_eq!(strict.workers[0].tasks, tasks.len());
+    }
+
+    #[test]
+    fn test_panics_are_caught_quietly() {
+        struct Panicking;
+
+        impl Task for Panicking {
+            fn run(&self, _: &LoadModel, _: &CancellationToken) -> Result<TaskOutput, TaskError> {
+                panic!("task bug")
+            }
+        }
+
+        install_quiet_panic_hook();
+        let result = run_isolated(&Panicking, &TaskSettings::DEFAULT, &CancellationToken::new());
+        assert_eq!(result, Err(TaskError::Panicked("task bug".into())));
+        // The hook only stays quiet while a task is running
+        assert!(!ISOLATING.get());
+    }
+
+    #[test]
+    fn test_imbalance() {
+        let busy = |millis| WorkerStats { busy: Duration::from_millis(millis), ..WorkerStats::default() };
+        let mut result = ExecutionResult::from_records(Duration::from_millis(10), Vec::new());
+        assert_eq!(result.imbalance(), None);
+        result.workers = vec![busy(4), busy(4)];
+        assert_eq!(result.imbalance(), Some(1.0));
+        result.workers = vec![busy(6), busy(2), busy(0), busy(0)];
+        assert_eq!(result.imbalance(), Some(3.0));
+    }
+}
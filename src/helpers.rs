@@ -1,5 +1,20 @@
 /// Collection of helper functions for mathematical tasks.
 
+use crate::cancel::CancellationToken;
+use crate::task::TaskError;
+
+/// Largest `n` for which `fibonacci(n)` fits in a `u64`.
+pub const FIBONACCI_MAX_N: u64 = 92;
+
+/// Largest `n` for which `factorial(n)` fits in a `u64`.
+pub const FACTORIAL_MAX_N: u64 = 20;
+
+/// Largest modulus `mod_exp` accepts without overflowing its intermediate products.
+pub const MOD_EXP_MAX_MODULUS: u64 = 1 << 32;
+
+/// Iterations long-running helpers do between checks of their cancellation token.
+const CANCEL_CHECK_INTERVAL: u64 = 4096;
+
 /// Calculate fibonnaci sequence recursively
 pub fn fibonacci(n: u64) -> u64 {
     if n <= 0 {
@@ -42,19 +57,22 @@ pub fn multiply<T: std::ops::Mul<Output = T>>(a: T, b: T) -> T{
     a * b
 }
 
-// determines if unsigned integer is a prime number 
-pub fn prime_check( n: u32 ) -> bool{
+// determines if unsigned integer is a prime number, stopping early if `cancel` fires
+pub fn prime_check( n: u32, cancel: &CancellationToken ) -> Result<bool, TaskError>{
     if n < 2 {
-        return false;
+        return Ok(false);
     }
 
     for i in 2..((n/2)+1) {
+        if (i as u64).is_multiple_of(CANCEL_CHECK_INTERVAL) {
+            cancel.check()?;
+        }
         // Uses iterator " 2..((n/2)+1) " which gets all values between 2 and half of n + 1
         if i * (n/i) == n {
-            return false;
+            return Ok(false);
         }
     }
-    true
+    Ok(true)
 }
 
 // Modulo exponentiation
@@ -93,6 +111,11 @@ pub fn mod_exp(mut base: u64, mut exp: u64, modulus: u64) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
+
+    fn never() -> CancellationToken {
+        CancellationToken::new()
+    }
 
     #[test]
     fn test_fibonacci() {
@@ -108,6 +131,13 @@ mod tests {
         assert_eq!(fibonacci(30), 1346269);
     }
 
+    #[test]
+    fn test_limits_fit_in_u64() {
+        assert_eq!(fibonacci(FIBONACCI_MAX_N), 12200160415121876738);
+        assert_eq!(factorial(FACTORIAL_MAX_N), 2432902008176640000);
+        assert_eq!(mod_exp(MOD_EXP_MAX_MODULUS - 1, 2, MOD_EXP_MAX_MODULUS), 1);
+    }
+
     #[test]
     fn test_factorial() {
         assert_eq!(factorial(0), 1);     // 0! = 1
@@ -119,19 +149,31 @@ mod tests {
     #[test]
     fn test_prime_check() {
         // Prime numbers
-    assert_eq!(prime_check(2), true);
-    assert_eq!(prime_check(3), true);
-    assert_eq!(prime_check(5), true);
-    assert_eq!(prime_check(7), true);
-    assert_eq!(prime_check(11), true);
-    assert_eq!(prime_check(97), true);
+    assert_eq!(prime_check(2, &never()), Ok(true));
+    assert_eq!(prime_check(3, &never()), Ok(true));
+    assert_eq!(prime_check(5, &never()), Ok(true));
+    assert_eq!(prime_check(7, &never()), Ok(true));
+    assert_eq!(prime_check(11, &never()), Ok(true));
+    assert_eq!(prime_check(97, &never()), Ok(true));
 
     // Non-prime numbers
-    assert_eq!(prime_check(0), false);
-    assert_eq!(prime_check(1), false);
-    assert_eq!(prime_check(4), false);
-    assert_eq!(prime_check(9), false);
-    assert_eq!(prime_check(100), false);
+    assert_eq!(prime_check(0, &never()), Ok(false));
+    assert_eq!(prime_check(1, &never()), Ok(false));
+    assert_eq!(prime_check(4, &never()), Ok(false));
+    assert_eq!(prime_check(9, &never()), Ok(false));
+    assert_eq!(prime_check(100, &never()), Ok(false));
+    }
+
+    #[test]
+    fn test_long_helpers_stop_when_cancelled() {
+        let expired = CancellationToken::with_timeout(Some(Duration::ZERO));
+        // 4294967291 is the largest prime below 2^32, so only the token can stop the loop early
+        assert_eq!(prime_check(4294967291, &expired), Err(TaskError::Timeout));
+        let cancelled = never();
+        cancelled.cancel();
+        assert_eq!(prime_check(4294967291, &cancelled), Err(TaskError::Cancelled));
+        // Small inputs finish before the first check
+        assert_eq!(prime_check(97, &expired), Ok(true));
     }
 
     #[test]
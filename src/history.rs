@@ -0,0 +1,366 @@
+//! A local history of benchmark reports and regression checks against it.
+//!
+//! The history is an append-only JSON Lines file: every line is a `HistoryEntry` holding
+//! one `BenchmarkReport` and the machine it ran on. A run is compared against the most
+//! recent entry for the same machine, workload manifest, mode, thread count and settings that
+//! has at least two samples per mode.
+
+use crate::bench::t_critical_95;
+use crate::report::BenchmarkReport;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// History file used by `compare` when `--history` is not given.
+pub const DEFAULT_HISTORY_PATH: &str = "bench-history.jsonl";
+
+/// Smallest relative change in mean duration reported as a regression or improvement,
+/// so statistically significant but negligible differences are not flagged.
+const MIN_CHANGE: f64 = 0.05;
+
+/// Identifies the machine a benchmark ran on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Machine {
+    pub hostname: String,
+    pub os: String,
+    pub arch: String,
+    pub cpus: usize,
+}
+
+impl Machine {
+    /// Describes the machine this process is running on.
+    pub fn current() -> Machine {
+        let hostname = fs::read_to_string("/etc/hostname")
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .or_else(|| std::env::var("COMPUTERNAME").ok())
+            .unwrap_or_else(|| "unknown".into());
+        Machine {
+            hostname,
+            os: std::env::consts::OS.into(),
+            arch: std::env::consts::ARCH.into(),
+            cpus: num_cpus::get(),
+        }
+    }
+}
+
+/// One stored benchmark run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub machine: Machine,
+    pub report: BenchmarkReport,
+}
+
+impl HistoryEntry {
+    /// Whether two entries measured the same workload the same way on the same machine,
+    /// with the same timeouts, retries and executor tuning, so that their durations can be
+    /// compared.
+    pub fn is_comparable(&self, other: &HistoryEntry) -> bool {
+        let (a, b) = (&self.report.config, &other.report.config);
+        self.machine == other.machine
+            && a.workload.is_some()
+            && a.workload == b.workload
+            && a.mode == b.mode
+            && a.threads == b.threads
+            && a.settings == b.settings
+    }
+
+    /// Whether every mode of the entry has the two or more samples a significance test
+    /// needs, which rules out single-sample `run` entries as baselines.
+    pub fn has_enough_samples(&self) -> bool {
+        let results = &self.report.results;
+        !results.is_empty() && results.iter().all(|result| result.samples_ns.len() >= 2)
+    }
+}
+
+/// Appends an entry to the history file, creating it if needed.
+pub fn append(path: &Path, entry: &HistoryEntry) -> Result<(), String> {
+    let line = serde_json::to_string(entry).expect("History entries are always serializable");
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(|e| format!("Failed to write history '{}': {}", path.display(), e))
+}
+
+/// Reads every entry of a history file, oldest first.
+pub fn load(path: &Path) -> Result<Vec<HistoryEntry>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read history '{}': {}", path.display(), e))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line).map_err(|e| format!("{}: line {}: {}", path.display(), number + 1, e))
+        })
+        .collect()
+}
+
+/// The most recent entry in `history` that `current` can be compared against, skipping
+/// entries with too few samples to test.
+pub fn find_baseline<'a>(history: &'a [HistoryEntry], current: &HistoryEntry) -> Option<&'a HistoryEntry> {
+    history.iter().rev().find(|entry| entry.is_comparable(current) && entry.has_enough_samples())
+}
+
+/// Whether a mode got slower, faster or stayed the same relative to the baseline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Significantly and noticeably slower than the baseline.
+    Regression,
+    /// Significantly and noticeably faster than the baseline.
+    Improvement,
+    /// Any difference is within noise or below `MIN_CHANGE`.
+    NoChange,
+    /// One side has fewer than two samples, so significance cannot be tested.
+    TooFewSamples,
+}
+
+impl Verdict {
+    fn label(&self) -> &'static str {
+        match self {
+            Verdict::Regression => "REGRESSION",
+            Verdict::Improvement => "improvement",
+            Verdict::NoChange => "no significant change",
+            Verdict::TooFewSamples => "too few samples",
+        }
+    }
+}
+
+/// How one mode's durations compare with the baseline's.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    /// `serial` or the executor key.
+    pub executor: String,
+    pub baseline_mean: Duration,
+    pub current_mean: Duration,
+    /// Relative change in mean duration; positive means slower.
+    pub change: f64,
+    pub verdict: Verdict,
+}
+
+/// Compares every mode present in both reports.
+///
+/// A mode regressed (or improved) when Welch's t-test finds its mean duration differs from
+/// the baseline at the 95% level and the change is at least `MIN_CHANGE`.
+pub fn compare(baseline: &BenchmarkReport, current: &BenchmarkReport) -> Vec<Comparison> {
+    current
+        .results
+        .iter()
+        .filter_map(|result| {
+            let old = baseline.results.iter().find(|old| old.executor == result.executor)?;
+            let change = (result.mean_ns as f64 - old.mean_ns as f64) / old.mean_ns.max(1) as f64;
+            let verdict = match welch_significant(&old.samples_ns, &result.samples_ns) {
+                None => Verdict::TooFewSamples,
+                Some(true) if change >= MIN_CHANGE => Verdict::Regression,
+                Some(true) if change <= -MIN_CHANGE => Verdict::Improvement,
+                Some(_) => Verdict::NoChange,
+            };
+            Some(Comparison {
+                executor: result.executor.clone(),
+                baseline_mean: Duration::from_nanos(old.mean_ns),
+                current_mean: Duration::from_nanos(result.mean_ns),
+                change,
+                verdict,
+            })
+        })
+        .collect()
+}
+
+/// Two-sided Welch's t-test at the 95% level.
+///
+/// # Returns
+/// `Some(true)` if the two samples' means differ significantly, or `None` if either
+/// sample has fewer than two values.
+pub fn welch_significant(a: &[u64], b: &[u64]) -> Option<bool> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (mean_a, var_a) = mean_and_variance(a);
+    let (mean_b, var_b) = mean_and_variance(b);
+    let (se_a, se_b) = (var_a / a.len() as f64, var_b / b.len() as f64);
+    let se = (se_a + se_b).sqrt();
+    if se == 0.0 {
+        // Both samples are constant, so any difference at all is real
+        return Some(mean_a != mean_b);
+    }
+    let t = (mean_a - mean_b).abs() / se;
+
+    // Welch–Satterthwaite approximation of the degrees of freedom
+    let df = (se_a + se_b).powi(2)
+        / (se_a.powi(2) / (a.len() - 1) as f64 + se_b.powi(2) / (b.len() - 1) as f64);
+    Some(t > t_critical_95((df.floor() as usize).max(1)))
+}
+
+/// Mean and sample variance (divides by `n - 1`) of at least two values.
+fn mean_and_variance(values: &[u64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let variance = values.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+/// Whether any compared mode regressed significantly.
+pub fn has_regression(comparisons: &[Comparison]) -> bool {
+    comparisons.iter().any(|c| c.verdict == Verdict::Regression)
+}
+
+/// Whether no mode could be tested for a regression, because none was present in both
+/// reports or every one had too few samples.
+pub fn is_inconclusive(comparisons: &[Comparison]) -> bool {
+    comparisons.iter().all(|c| c.verdict == Verdict::TooFewSamples)
+}
+
+/// One line per compared mode, followed by an overall verdict.
+pub fn display_comparison<'a>(baseline: &'a HistoryEntry, comparisons: &'a [Comparison]) -> impl fmt::Display + 'a {
+    fmt::from_fn(move |f| {
+        writeln!(f, "\n=== Comparison with baseline from {} (timestamp {}) ===",
+            baseline.machine.hostname, baseline.report.timestamp)?;
+        writeln!(f, "{:<16}{:>14}{:>14}{:>10}   Verdict", "Mode", "Baseline", "Current", "Change")?;
+        for comparison in comparisons {
+            writeln!(f, "{:<16}{:>14}{:>14}{:>+9.1}%   {}",
+                comparison.executor,
+                format!("{:.2?}", comparison.baseline_mean),
+                format!("{:.2?}", comparison.current_mean),
+                comparison.change * 100.0,
+                comparison.verdict.label())?;
+        }
+
+        let regressions = comparisons.iter().filter(|c| c.verdict == Verdict::Regression).count();
+        if is_inconclusive(comparisons) {
+            writeln!(f, "⚠️  No mode had enough samples to test for regressions; use --repetitions 2 or more.")
+        } else if regressions == 0 {
+            writeln!(f, "No significant regressions.")
+        } else {
+            writeln!(f, "⚠️  {} mode(s) regressed significantly.", regressions)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{ModeReport, ReportConfig, ReportSettings};
+    use crate::workload::{WorkloadManifest, WorkloadSpec};
+
+    fn mode(executor: &str, samples_ns: &[u64]) -> ModeReport {
+        let mean_ns = samples_ns.iter().sum::<u64>() / samples_ns.len() as u64;
+        ModeReport {
+            executor: executor.into(),
+            samples_ns: samples_ns.to_vec(),
+            mean_ns,
+            median_ns: mean_ns,
+            std_dev_ns: 0,
+            min_ns: 0,
+            max_ns: 0,
+            ci95_ns: (0, 0),
+            speedup: 1.0,
+            errors: 0,
+            mismatches: 0,
+            retries: 0,
+            panics: Vec::new(),
+        }
+    }
+
+    fn entry(threads: u32, results: Vec<ModeReport>) -> HistoryEntry {
+        let config = ReportConfig {
+            mode: "none".into(),
+            batch_size: 100,
+            workload: Some(WorkloadManifest { seed: 1, batch_size: 100, spec: WorkloadSpec::uniform() }),
+            cpus: 4,
+            threads,
+            warmup: 1,
+            repetitions: 5,
+            settings: ReportSettings::default(),
+        };
+        HistoryEntry {
+            machine: Machine { hostname: "bench-box".into(), os: "linux".into(), arch: "x86_64".into(), cpus: 4 },
+            report: BenchmarkReport { timestamp: 0, config, results },
+        }
+    }
+
+    #[test]
+    fn test_welch_significance() {
+        assert_eq!(welch_significant(&[100, 101, 99, 100], &[100, 99, 101, 100]), Some(false));
+        assert_eq!(welch_significant(&[100, 101, 99, 100], &[150, 149, 151, 150]), Some(true));
+        assert_eq!(welch_significant(&[100], &[150, 149]), None);
+        assert_eq!(welch_significant(&[100, 100], &[100, 100]), Some(false));
+    }
+
+    #[test]
+    fn test_compare_verdicts() {
+        let baseline = entry(4, vec![
+            mode("serial", &[1000, 1010, 990, 1000]),
+            mode("mutex", &[1000, 1010, 990, 1000]),
+            mode("pool", &[1000, 1010, 990, 1000]),
+            mode("channel", &[1000, 1010, 990, 1000]),
+        ]);
+        let current = entry(4, vec![
+            mode("serial", &[1001, 1009, 991, 1000]),
+            mode("mutex", &[1500, 1510, 1490, 1500]),
+            mode("pool", &[500, 510, 490, 500]),
+            mode("atomic", &[500, 510, 490, 500]),
+        ]);
+        let verdicts: Vec<_> = compare(&baseline.report, &current.report)
+            .into_iter()
+            .map(|c| (c.executor, c.verdict))
+            .collect();
+        assert_eq!(verdicts, vec![
+            ("serial".to_string(), Verdict::NoChange),
+            ("mutex".to_string(), Verdict::Regression),
+            ("pool".to_string(), Verdict::Improvement),
+        ]);
+    }
+
+    #[test]
+    fn test_find_baseline_and_round_trip() {
+        let path = std::env::temp_dir().join(format!("history-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let older = entry(4, vec![mode("serial", &[1, 2])]);
+        let other_threads = entry(2, vec![mode("serial", &[3, 4])]);
+        let newer = entry(4, vec![mode("serial", &[5, 6])]);
+        for stored in [&older, &other_threads, &newer] {
+            append(&path, stored).unwrap();
+        }
+        let history = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(history, vec![older, other_threads, newer.clone()]);
+        assert_eq!(find_baseline(&history, &entry(4, Vec::new())), Some(&newer));
+        assert_eq!(find_baseline(&history, &entry(8, Vec::new())), None);
+    }
+
+    #[test]
+    fn test_baseline_needs_samples() {
+        let benched = entry(4, vec![mode("serial", &[1, 2]), mode("mutex", &[1, 2])]);
+        let ran = entry(4, vec![mode("serial", &[3]), mode("mutex", &[3])]);
+        let history = vec![benched.clone(), ran];
+        assert_eq!(find_baseline(&history, &entry(4, Vec::new())), Some(&benched));
+
+        let comparisons = compare(&history[1].report, &benched.report);
+        assert!(comparisons.iter().all(|c| c.verdict == Verdict::TooFewSamples));
+        assert!(is_inconclusive(&comparisons) && !has_regression(&comparisons));
+        assert!(display_comparison(&history[1], &comparisons).to_string().contains("No mode had enough samples"));
+        assert!(!is_inconclusive(&compare(&benched.report, &benched.report)));
+    }
+
+    #[test]
+    fn test_baseline_needs_same_settings() {
+        let mut retried = entry(4, vec![mode("serial", &[1, 2])]);
+        retried.report.config.settings.retry = Some("up to 3 attempts".into());
+        let plain = entry(4, vec![mode("serial", &[3, 4])]);
+        let history = vec![plain.clone(), retried.clone()];
+
+        // The newest entry retried transient failures, so a run without retries skips it
+        assert_eq!(find_baseline(&history, &entry(4, Vec::new())), Some(&plain));
+        let mut current = entry(4, Vec::new());
+        current.report.config.settings = ReportSettings { chunk_size: Some(16), ..retried.report.config.settings.clone() };
+        assert_eq!(find_baseline(&history, &current), None);
+        current.report.config.settings.chunk_size = None;
+        assert_eq!(find_baseline(&history, &current), Some(&retried));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::executor::ExecutionResult;
+use crate::priority::{Priorities, Priority};
+use crate::task::TaskType;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Sub-buckets per power of two; 8 keeps every bucket within 12.5% of its values.
+const SUB_BUCKETS: u64 = 8;
+
+/// Enough buckets to cover every `u64` nanosecond value.
+const BUCKET_COUNT: usize = 8 + 61 * SUB_BUCKETS as usize;
+
+/// Characters used to draw the histogram sparkline, from fewest to most samples.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A log-linear latency histogram with nanosecond resolution.
+///
+/// Values below 8ns get an exact bucket each. Above that, every power of two is split
+/// into 8 equal sub-buckets, so memory is fixed while the relative error stays bounded.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    sum_nanos: u128,
+    max_nanos: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram { counts: vec![0; BUCKET_COUNT], total: 0, sum_nanos: 0, max_nanos: 0 }
+    }
+}
+
+impl Histogram {
+    /// Adds one latency sample.
+    pub fn record(&mut self, latency: Duration) {
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        self.counts[bucket_index(nanos)] += 1;
+        self.total += 1;
+        self.sum_nanos += nanos as u128;
+        self.max_nanos = self.max_nanos.max(nanos);
+    }
+
+    /// Number of recorded samples.
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// Mean of the recorded samples, or zero if there are none.
+    pub fn mean(&self) -> Duration {
+        if self.total == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos((self.sum_nanos / self.total as u128) as u64)
+    }
+
+    /// Largest recorded sample.
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max_nanos)
+    }
+
+    /// The latency at or below which a fraction `q` (0.0–1.0) of samples fall.
+    ///
+    /// Reports the upper edge of the bucket holding that sample, capped at the maximum
+    /// recorded value, so the result never understates the true percentile.
+    pub fn percentile(&self, q: f64) -> Duration {
+        if self.total == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((q * self.total as f64).ceil() as u64).clamp(1, self.total);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Duration::from_nanos(bucket_upper(index).min(self.max_nanos));
+            }
+        }
+        self.max()
+    }
+
+    /// Draws the distribution as one bar per power of two between the fastest and
+    /// slowest sample, scaled to the fullest bar.
+    pub fn sparkline(&self) -> String {
+        let mut octaves = [0u64; 64];
+        for (index, count) in self.counts.iter().enumerate() {
+            octaves[octave_of(index)] += count;
+        }
+        let first = octaves.iter().position(|&count| count > 0);
+        let last = octaves.iter().rposition(|&count| count > 0);
+        let (Some(first), Some(last)) = (first, last) else {
+            return String::new();
+        };
+        let peak = octaves[first..=last].iter().copied().max().unwrap_or(1);
+        octaves[first..=last]
+            .iter()
+            .map(|&count| match count {
+                0 => ' ',
+                _ => BARS[((count * (BARS.len() as u64 - 1)) / peak) as usize],
+            })
+            .collect()
+    }
+}
+
+/// Maps a nanosecond value onto its bucket.
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS {
+        return nanos as usize;
+    }
+    let exponent = 63 - nanos.leading_zeros() as u64; // floor(log2(nanos)), at least 3
+    let shift = exponent - 3;
+    let sub = (nanos >> shift) - SUB_BUCKETS;
+    (SUB_BUCKETS + shift * SUB_BUCKETS + sub) as usize
+}
+
+/// The largest nanosecond value that falls into `index`.
+fn bucket_upper(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        return index;
+    }
+    let shift = (index - SUB_BUCKETS) / SUB_BUCKETS;
+    let sub = (index - SUB_BUCKETS) % SUB_BUCKETS;
+    let lower = (SUB_BUCKETS + sub) << shift;
+    lower + ((1u64 << shift) - 1)
+}
+
+/// The power of two (floor(log2)) that the values in bucket `index` belong to.
+fn octave_of(index: usize) -> usize {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        // Exact buckets: 0 and 1 share the lowest octave
+        return (63 - index.max(1).leading_zeros() as u64) as usize;
+    }
+    (3 + (index - SUB_BUCKETS) / SUB_BUCKETS) as usize
+}
+
+/// Queue-wait and run-time histograms for one group of tasks.
+#[derive(Clone, Debug, Default)]
+pub struct LatencyStats {
+    pub wait: Histogram,
+    pub run: Histogram,
+}
+
+/// Per-`TaskType` and per-priority latency histograms for one execution, plus an overall total.
+pub struct LatencyReport {
+    /// Keyed by `TaskType::key`.
+    pub by_type: BTreeMap<&'static str, LatencyStats>,
+    /// Keyed by the priority of each task's kind.
+    pub by_priority: BTreeMap<Priority, LatencyStats>,
+    pub overall: LatencyStats,
+}
+
+impl LatencyReport {
+    /// Aggregates the per-task timings of `result`, which must come from running `tasks`,
+    /// grouping them by task type and by the priority `priorities` gives each task.
+    pub fn from_execution(tasks: &[TaskType], result: &ExecutionResult, priorities: &Priorities) -> LatencyReport {
+        let mut by_type: BTreeMap<&'static str, LatencyStats> = BTreeMap::new();
+        let mut by_priority: BTreeMap<Priority, LatencyStats> = BTreeMap::new();
+        let mut overall = LatencyStats::default();
+        for (index, (task, timing)) in tasks.iter().zip(&result.timings).enumerate() {
+            let stats = by_type.entry(task.key()).or_default();
+            let priority = by_priority.entry(priorities.of(index, task)).or_default();
+            for stats in [stats, priority, &mut overall] {
+                stats.wait.record(timing.wait);
+                stats.run.record(timing.run);
+            }
+        }
+        LatencyReport { by_type, by_priority, overall }
+    }
+
+    /// Run-time percentiles and a histogram per task type, followed by queue-wait
+    /// percentiles, as a table headed with `label`.
+    pub fn display<'a>(&'a self, label: &'a str) -> impl fmt::Display + 'a {
+        fmt::from_fn(move |f| {
+            writeln!(f, "\n--- Per-task latency: {} ---", label)?;
+            writeln!(f, "{:<24}{:>8}{:>11}{:>11}{:>11}{:>11}{:>11}{:>11}   Histogram (log2 buckets)",
+                "Run time", "Count", "Mean", "p50", "p90", "p99", "p999", "Max")?;
+            for (key, stats) in self.rows() {
+                write_row(f, key, &stats.run, true)?;
+            }
+            writeln!(f, "{:<24}{:>8}{:>11}{:>11}{:>11}{:>11}{:>11}{:>11}",
+                "Queue wait", "Count", "Mean", "p50", "p90", "p99", "p999", "Max")?;
+            for (key, stats) in self.rows() {
+                write_row(f, key, &stats.wait, false)?;
+            }
+
+            // Only worth a table when the batch mixes priorities; most urgent first
+            if self.by_priority.len() > 1 {
+                writeln!(f, "{:<24}{:>8}{:>11}{:>11}{:>11}{:>11}{:>11}{:>11}",
+                    "Queue wait by priority", "Count", "Mean", "p50", "p90", "p99", "p999", "Max")?;
+                for (priority, stats) in self.by_priority.iter().rev() {
+                    write_row(f, priority.label(), &stats.wait, false)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Each task type followed by the overall total.
+    fn rows(&self) -> impl Iterator<Item = (&str, &LatencyStats)> {
+        self.by_type
+            .iter()
+            .map(|(key, stats)| (*key, stats))
+            .chain(std::iter::once(("all tasks", &self.overall)))
+    }
+}
+
+fn write_row(f: &mut fmt::Formatter<'_>, key: &str, histogram: &Histogram, with_sparkline: bool) -> fmt::Result {
+    let cell = |duration: Duration| format!("{:.1?}", duration);
+    write!(f, "{:<24}{:>8}{:>11}{:>11}{:>11}{:>11}{:>11}{:>11}",
+        format!("  {}", key),
+        histogram.count(),
+        cell(histogram.mean()),
+        cell(histogram.percentile(0.50)),
+        cell(histogram.percentile(0.90)),
+        cell(histogram.percentile(0.99)),
+        cell(histogram.percentile(0.999)),
+        cell(histogram.max()))?;
+    if with_sparkline {
+        write!(f, "   {}", histogram.sparkline())?;
+    }
+    writeln!(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds_contain_values() {
+        for nanos in [0, 1, 7, 8, 9, 15, 16, 17, 100, 1_000, 123_456_789, u64::MAX / 3, u64::MAX] {
+            let index = bucket_index(nanos);
+            assert!(index < BUCKET_COUNT);
+            assert!(nanos <= bucket_upper(index), "{} above its bucket", nanos);
+            // Values are never more than 12.5% below their bucket's upper edge
+            assert!(bucket_upper(index) - nanos <= nanos / 8, "{} bucket too wide", nanos);
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut histogram = Histogram::default();
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.max(), Duration::from_micros(1000));
+        for (q, exact) in [(0.5, 500.0), (0.9, 900.0), (0.99, 990.0)] {
+            let reported = histogram.percentile(q).as_secs_f64() * 1e6;
+            assert!(reported >= exact && reported <= exact * 1.125, "p{} = {}", q, reported);
+        }
+        assert_eq!(histogram.percentile(1.0), Duration::from_micros(1000));
+    }
+
+    #[test]
+    fn test_empty_histogram() {
+        let histogram = Histogram::default();
+        assert_eq!(histogram.percentile(0.99), Duration::ZERO);
+        assert_eq!(histogram.mean(), Duration::ZERO);
+        assert_eq!(histogram.sparkline(), "");
+    }
+}
@@ -0,0 +1,293 @@
+//! Simulated per-task latency.
+//!
+//! A `LoadModel` draws a delay from a distribution before every task runs, and either
+//! sleeps through it to emulate I/O-bound work or spins through it to emulate CPU-bound
+//! work. It can also fail a fraction of tasks with a transient error to emulate flaky work.
+//! The model is applied inside `Task::run`, so every executor pays it identically.
+
+use crate::cancel::CancellationToken;
+use crate::task::TaskError;
+use rand::Rng;
+use std::fmt;
+use std::hint;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest delay `LoadModel::sample` returns; the exponential and log-normal tails are cut
+/// off here rather than overflowing `Duration`.
+pub const MAX_DELAY: Duration = Duration::from_secs(3600);
+
+/// The distribution each task's simulated delay is drawn from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delay {
+    /// No delay at all.
+    None,
+    /// The same delay for every task.
+    Fixed(Duration),
+    /// Uniformly distributed between the two bounds, inclusive.
+    Uniform(Duration, Duration),
+    /// Exponentially distributed with the given mean, like arrivals of independent events.
+    Exponential(Duration),
+    /// Log-normally distributed with the given median and shape `sigma`, giving a long tail.
+    LogNormal { median: Duration, sigma: f64 },
+}
+
+/// How a task spends its simulated delay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wait {
+    /// Sleep, freeing the CPU like a task blocked on I/O.
+    Sleep,
+    /// Busy-spin, occupying the CPU like a compute-bound task.
+    Spin,
+}
+
+/// The simulated load added to every task.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoadModel {
+    pub delay: Delay,
+    pub wait: Wait,
+    /// Probability that a task fails with `TaskError::Transient` after its delay.
+    pub failure_rate: f64,
+}
+
+impl LoadModel {
+    /// No simulated load.
+    pub const NONE: LoadModel = LoadModel { delay: Delay::None, wait: Wait::Sleep, failure_rate: 0.0 };
+
+    /// The load used by `--mode simulate`: a fixed 100µs sleep before every task.
+    pub const SIMULATED: LoadModel =
+        LoadModel { delay: Delay::Fixed(Duration::from_micros(100)), wait: Wait::Sleep, failure_rate: 0.0 };
+
+    /// Whether this model adds no delay.
+    pub fn is_none(&self) -> bool {
+        self.delay == Delay::None
+    }
+
+    /// Draws one task's delay, at most `MAX_DELAY`.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        match self.delay {
+            Delay::None => Duration::ZERO,
+            Delay::Fixed(delay) => delay,
+            Delay::Uniform(low, high) => rng.gen_range(low..=high),
+            Delay::Exponential(mean) => {
+                // Inverse transform sampling; 1 - u is never zero, so the log is finite
+                let u: f64 = rng.gen_range(0.0..1.0);
+                scale(mean, -(1.0 - u).ln())
+            }
+            Delay::LogNormal { median, sigma } => {
+                // Box–Muller gives a standard normal from two uniforms
+                let (u1, u2): (f64, f64) = (rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0));
+                let normal = (-2.0 * (1.0 - u1).ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                scale(median, (sigma * normal).exp())
+            }
+        }
+    }
+
+    /// Draws a delay and waits it out, sleeping or spinning as configured, then fails the
+    /// task with probability `failure_rate`.
+    ///
+    /// # Returns
+    /// * The error from `cancel.check()` if the token fires before the delay is over.
+    /// * `Err(TaskError::Transient)` if the task was picked to fail.
+    pub fn apply(&self, cancel: &CancellationToken) -> Result<(), TaskError> {
+        if self.is_none() && self.failure_rate == 0.0 {
+            return Ok(());
+        }
+        let mut rng = rand::thread_rng();
+        self.wait_out(self.sample(&mut rng), cancel)?;
+        if self.failure_rate > 0.0 && rng.gen_bool(self.failure_rate) {
+            return Err(TaskError::Transient("simulated failure".into()));
+        }
+        Ok(())
+    }
+
+    /// Sleeps or spins through `delay`, stopping early if `cancel` fires.
+    fn wait_out(&self, delay: Duration, cancel: &CancellationToken) -> Result<(), TaskError> {
+        if delay.is_zero() {
+            return Ok(());
+        }
+        match self.wait {
+            Wait::Sleep => {
+                // Never sleep past the deadline, so a timed-out task stops on time
+                thread::sleep(cancel.remaining().map_or(delay, |remaining| remaining.min(delay)));
+                cancel.check()
+            }
+            Wait::Spin => {
+                let deadline = Instant::now() + delay;
+                while Instant::now() < deadline {
+                    cancel.check()?;
+                    hint::spin_loop();
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Parses a `--load` value: `none`, `fixed:100us`, `uniform:50us:150us`, `exp:100us`
+    /// or `lognormal:100us:0.5`. The model sleeps; use `with_wait` to make it spin.
+    pub fn parse(raw: &str) -> Result<LoadModel, String> {
+        let parts: Vec<&str> = raw.split(':').map(str::trim).collect();
+        let duration = |i: usize| {
+            parts.get(i).and_then(|part| parse_duration(part)).ok_or_else(|| {
+                format!("Invalid load '{}'; expected a duration such as 250ns, 100us, 1.5ms or 2s.", raw)
+            })
+        };
+        let delay = match (parts[0], parts.len()) {
+            ("none", 1) => Delay::None,
+            ("fixed", 2) => Delay::Fixed(duration(1)?),
+            ("uniform", 3) => {
+                let (low, high) = (duration(1)?, duration(2)?);
+                if low > high {
+                    return Err(format!("Invalid load '{}'; the lower bound is above the upper bound.", raw));
+                }
+                Delay::Uniform(low, high)
+            }
+            ("exp", 2) => Delay::Exponential(duration(1)?),
+            ("lognormal", 3) => {
+                let sigma = parts[2].parse::<f64>().ok().filter(|sigma| sigma.is_finite() && *sigma >= 0.0);
+                let sigma = sigma.ok_or_else(|| format!("Invalid load '{}'; sigma must be a non-negative number.", raw))?;
+                Delay::LogNormal { median: duration(1)?, sigma }
+            }
+            _ => {
+                return Err(format!(
+                    "Invalid load '{}'; expected none, fixed:D, uniform:D:D, exp:D or lognormal:D:SIGMA.",
+                    raw
+                ))
+            }
+        };
+        Ok(LoadModel { delay, ..LoadModel::NONE })
+    }
+
+    /// This model with its delay spent as `wait`.
+    pub fn with_wait(self, wait: Wait) -> LoadModel {
+        LoadModel { wait, ..self }
+    }
+
+    /// This model failing each task with probability `failure_rate`, which must be in `0..=1`.
+    pub fn with_failure_rate(self, failure_rate: f64) -> LoadModel {
+        LoadModel { failure_rate, ..self }
+    }
+}
+
+/// `base * factor`, capped at `MAX_DELAY` when the product is too large (or infinite).
+fn scale(base: Duration, factor: f64) -> Duration {
+    Duration::try_from_secs_f64(base.as_secs_f64() * factor).map_or(MAX_DELAY, |delay| delay.min(MAX_DELAY))
+}
+
+impl fmt::Display for LoadModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.delay {
+            Delay::None => write!(f, "none")?,
+            Delay::Fixed(delay) => write!(f, "fixed {:?}", delay)?,
+            Delay::Uniform(low, high) => write!(f, "uniform {:?}..{:?}", low, high)?,
+            Delay::Exponential(mean) => write!(f, "exponential mean {:?}", mean)?,
+            Delay::LogNormal { median, sigma } => write!(f, "log-normal median {:?} sigma {}", median, sigma)?,
+        }
+        match self.wait {
+            _ if self.is_none() => {}
+            Wait::Sleep => write!(f, " (sleep)")?,
+            Wait::Spin => write!(f, " (spin)")?,
+        }
+        if self.failure_rate > 0.0 {
+            write!(f, ", {}% transient failures", self.failure_rate * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a duration with a unit suffix such as `250ns`, `100us`, `1.5ms` or `2s`.
+pub fn parse_duration(raw: &str) -> Option<Duration> {
+    let split = raw.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (value, unit) = raw.split_at(split);
+    let value: f64 = value.parse().ok()?;
+    let seconds_per_unit = match unit {
+        "ns" => 1e-9,
+        "us" | "µs" => 1e-6,
+        "ms" => 1e-3,
+        "s" => 1.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(value * seconds_per_unit).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn mean_of(model: LoadModel, samples: u32) -> f64 {
+        let mut rng = StdRng::seed_from_u64(1);
+        (0..samples).map(|_| model.sample(&mut rng).as_secs_f64() * 1e6).sum::<f64>() / samples as f64
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(LoadModel::parse("none"), Ok(LoadModel::NONE));
+        assert_eq!(LoadModel::parse("fixed:100us"), Ok(LoadModel::SIMULATED));
+        assert_eq!(
+            LoadModel::parse("uniform:50us:1.5ms").unwrap().delay,
+            Delay::Uniform(Duration::from_micros(50), Duration::from_micros(1500))
+        );
+        assert_eq!(LoadModel::parse("exp:2s").unwrap().delay, Delay::Exponential(Duration::from_secs(2)));
+        assert_eq!(
+            LoadModel::parse("lognormal:250ns:0.5").unwrap().delay,
+            Delay::LogNormal { median: Duration::from_nanos(250), sigma: 0.5 }
+        );
+        for bad in ["fixed", "fixed:100", "fixed:-1ms", "uniform:2ms:1ms", "lognormal:1ms:-1", "gamma:1ms"] {
+            assert!(LoadModel::parse(bad).is_err(), "{} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_sample_distributions() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let uniform = LoadModel::parse("uniform:10us:20us").unwrap();
+        for _ in 0..1000 {
+            let delay = uniform.sample(&mut rng);
+            assert!(delay >= Duration::from_micros(10) && delay <= Duration::from_micros(20));
+        }
+        assert!((mean_of(LoadModel::parse("exp:100us").unwrap(), 20_000) - 100.0).abs() < 5.0);
+        // A log-normal's mean is median * exp(sigma² / 2)
+        let expected = 100.0 * (0.5f64 * 0.5 / 2.0).exp();
+        assert!((mean_of(LoadModel::parse("lognormal:100us:0.5").unwrap(), 20_000) - expected).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_heavy_tails_are_capped() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let wild = LoadModel::parse("lognormal:1us:40").unwrap();
+        let delays: Vec<Duration> = (0..1000).map(|_| wild.sample(&mut rng)).collect();
+        assert!(delays.iter().all(|delay| *delay <= MAX_DELAY));
+        assert!(delays.contains(&MAX_DELAY));
+        let long = LoadModel::parse("exp:1000000000s").unwrap();
+        assert!((0..100).all(|_| long.sample(&mut rng) <= MAX_DELAY));
+    }
+
+    #[test]
+    fn test_spin_waits_out_the_delay() {
+        let model = LoadModel::parse("fixed:200us").unwrap().with_wait(Wait::Spin);
+        let start = Instant::now();
+        assert_eq!(model.apply(&CancellationToken::new()), Ok(()));
+        assert!(start.elapsed() >= Duration::from_micros(200));
+
+        // A deadline cuts the delay short
+        let long = LoadModel::parse("fixed:10s").unwrap();
+        let start = Instant::now();
+        let expiring = CancellationToken::with_timeout(Some(Duration::from_millis(1)));
+        assert_eq!(long.apply(&expiring), Err(TaskError::Timeout));
+        assert_eq!(long.with_wait(Wait::Spin).apply(&expiring), Err(TaskError::Timeout));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(model.to_string(), "fixed 200µs (spin)");
+    }
+
+    #[test]
+    fn test_failure_rate() {
+        let cancel = CancellationToken::new();
+        assert_eq!(LoadModel::NONE.with_failure_rate(1.0).apply(&cancel).map_err(|e| e.kind()), Err("transient"));
+        let flaky = LoadModel::NONE.with_failure_rate(0.25);
+        let failures = (0..4000).filter(|_| flaky.apply(&cancel).is_err()).count();
+        assert!((800..1200).contains(&failures), "{} of 4000 tasks failed", failures);
+        assert_eq!(flaky.to_string(), "none, 25% transient failures");
+    }
+}
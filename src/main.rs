@@ -1,251 +1,322 @@
-use rand::{Rng, SeedableRng};
-use rand::rngs::StdRng;
-use crate::task::TaskType;
-use crate::task::Task;
-use std::time::{Instant, Duration};
-use std::sync::{Arc, Mutex};
-use std::collections::VecDeque;
-use std::thread;
-use std::io;
-
-mod task;
-mod helpers;
-
-/// Entry point for the program. Presents a CLI for configuring and benchmarking task execution.
-///
-/// Prompts the user to choose between two execution modes:
-/// 1. Default (concurrent execution using a mutex-protected queue)
-/// 2. Simulated task load (adds delay to simulate real-world task latency)
-///
-/// The user is then asked to specify:
-/// - The number of tasks to generate
-/// - The number of threads to use for concurrent execution (validated against CPU count)
+use cs354_rust::history::{self, HistoryEntry};
+use cs354_rust::sweep::sweep;
+use cs354_rust::{summary, taskfile, trace};
+use cs354_rust::{
+    benchmark, execute_dag, execute_serially, install_quiet_panic_hook, measure_executor, BenchmarkReport,
+    ExecutionResult, Executor, LatencyReport, Measurement, OutputFormat, ReportConfig, ReportSettings, TaskGraph,
+    TaskType,
+};
+use crate::cli::{Command, Options};
+use crate::prompt::prompt_for_options;
+use std::path::Path;
+use std::{env, process};
+
+mod cli;
+mod prompt;
+
+/// Entry point for the program. Configures and benchmarks task execution.
 ///
-/// The program generates a set of tasks and benchmarks both serial and concurrent execution.
-/// Finally, it compares and prints the time each approach took.
+/// When command-line arguments are given they are parsed by `cli::parse_args` (see
+/// `cli::USAGE`); otherwise the configuration is collected interactively.
 fn main() {
-    println!("Choose mode:");
-    println!("[1] Default (Mutex-based concurrency)");
-    println!("[2] Simulate realistic task load");
-
-    // Loop until the user enters a valid mode (1 or 2)
-    let mode = loop {
-        let m = prompt_for_u32("Enter choice:");
-        if m == 1 || m == 2 {
-            break m;
-        } else {
-            println!("Invalid mode. Please enter 1 or 2.");
-        }
-    };
-    
-    // Enable simulated load delay if user selects mode 2.
-    let simulate_load = mode == 2;
-
-    // Prompt for how many tasks to generate
-    let batch_size = prompt_for_u32("Enter number of tasks to generate:");
-    
-    // Get number of threads from user input.
-    let max_threads = num_cpus::get() as u32;
-    let thread_count = loop {
-        let count = prompt_for_u32(&format!("Enter number of threads [1-{}]:", max_threads));
-        if count > 0 && count <= max_threads {
-            break count;
-        } else {
-            println!("Please enter a number between 1 and {}.", max_threads);
+    // Panicking tasks are reported in the failure summary, so the hook need not print them
+    install_quiet_panic_hook();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = if args.is_empty() {
+        prompt_for_options()
+    } else {
+        match cli::parse_args(args) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{}\n\n{}", message, cli::USAGE);
+                process::exit(2);
+            }
         }
     };
+    run(options);
+}
 
-    println!("Generating {} tasks...", batch_size);
-    println!("Using {} threads for concurrent execution.", thread_count);
+/// Generates the batch described by `options` and runs the requested command on it.
+///
+/// The program generates a set of tasks and benchmarks serial execution and each selected
+/// concurrent executor. Finally, it prints timing statistics for each approach.
+fn run(mut options: Options) {
+    if options.command == Command::Help {
+        println!("{}", cli::USAGE);
+        return;
+    }
 
-    // Generate a set of tasks
-    let tasks = generate_tasks(batch_size);
+    if let Some(path) = &options.graph_path {
+        run_graph(path, &options);
+        return;
+    }
 
-    // Run the tasks serially and measure the execution time
-    println!("\n--- Running tasks serially ---");
-    let serial_duration = execute_serially(&tasks, simulate_load);
+    // Only the report goes to stdout when a machine-readable format was requested
+    let text = options.format == OutputFormat::Text;
+
+    // Load the batch from a task file, or generate a set of tasks
+    let (tasks, own_priorities) = match &options.input_path {
+        Some(path) => match taskfile::load_tasks(path) {
+            Ok((tasks, own_priorities)) => {
+                if text {
+                    println!("Loaded {} tasks from {}", tasks.len(), path.display());
+                }
+                (tasks, own_priorities)
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        None => {
+            if text {
+                println!("Generating workload: {}", options.workload);
+            }
+            (options.workload.generate(), Vec::new())
+        }
+    };
+    // Tasks that set their own priority in the task file override their kind's
+    options.settings.priorities = options.settings.priorities.with_task_priorities(own_priorities);
 
-    // Run the tasks concurrently and measure the execution time
-    println!("\n--- Running tasks concurrently ---");
-    let concurrent_duration = execute_concurrently(&tasks, thread_count, simulate_load);
+    // Record how the batch was generated so the run can be reproduced exactly
+    if let Some(path) = &options.manifest_path {
+        match options.workload.save(path) {
+            Ok(()) if text => println!("Wrote workload manifest to {}", path.display()),
+            Ok(()) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
 
-    // Compare and summarize both execution durations
-    compare_durations(serial_duration, concurrent_duration);
-}
+    if let Some(path) = &options.tasks_path {
+        match taskfile::save_tasks(path, &tasks, &options.settings.priorities) {
+            Ok(()) if text => println!("Wrote {} tasks to {}", tasks.len(), path.display()),
+            Ok(()) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
 
-/// Prompts the user for a positive integer and validates the input.
-///
-/// # Arguments
-/// * `prompt` - A string prompt to display to the user.
-///
-/// # Returns
-/// A validated, non-zero `u32` entered by the user.
-fn prompt_for_u32(prompt: &str) -> u32 {
-    loop {
-        println!("{}", prompt);
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            println!("Failed to read input. Try again.");
-            continue;
+    match options.command {
+        Command::Generate => {}
+        Command::Sweep => {
+            for &executor in &options.executors {
+                let progress = |threads| println!("Sweeping {} with {} thread(s)...", executor.name(), threads);
+                let result = sweep(&tasks, executor, &options.thread_counts, options.settings.clone(), &options.config, progress);
+                print!("{}", result);
+            }
         }
-        match input.trim().parse::<u32>() {
-            Ok(num) if num > 0 => return num, // Valid number > 0
-            Ok(_) => println!("Please enter a number greater than 0."), // Zero or negative
-            Err(_) => println!("Invalid number. Try again."), // Not a number
+        Command::Compare => {
+            let report = compare_executors(&tasks, &options);
+            let path = options.history_path.as_ref().expect("compare always has a history path");
+            if compare_with_history(path, HistoryEntry { machine: history::Machine::current(), report }) {
+                process::exit(1);
+            }
+        }
+        _ => {
+            let report = compare_executors(&tasks, &options);
+            if let Some(path) = &options.history_path {
+                let entry = HistoryEntry { machine: history::Machine::current(), report };
+                match history::append(path, &entry) {
+                    Ok(()) if text => println!("Appended results to history {}", path.display()),
+                    Ok(()) => {}
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
         }
     }
 }
 
-/// Generates a list of random tasks from all supported `TaskType` variants.
-/// It randomly chooses one of the task variants on each iteration,
-/// and generates suitable input values within reasonable ranges for each variant.
+/// Runs the task graph in `path` on a single worker and on `options.thread_counts[0]`
+/// workers, then verifies and summarizes both runs like `compare_executors`.
 ///
 /// # Arguments
-/// * `batch_size` - The total number of tasks to generate.
-///
-/// # Returns
-/// A `Vec<TaskType>` containing `batch_size` randomly generated tasks.
-pub fn generate_tasks(batch_size: u32) -> Vec<TaskType> {
-    let mut tasks = Vec::new();
-
-    // Use a seeded RNG for deterministic task generation
-    let mut rng = StdRng::seed_from_u64(42);
-
-    for _ in 0..batch_size {
-        let task_type = rng.gen_range(0..7);
-        let task = match task_type {
-            0 => TaskType::Compute {
-                a: rng.gen_range(1..100),
-                b: rng.gen_range(1..100),
-            },
-            1 => TaskType::Fibonacci {
-                n: rng.gen_range(1..30),
-            },
-            2 => TaskType::Divide {
-                numerator: rng.gen_range(1..100),
-                denominator: rng.gen_range(1..99) + 1, // Avoid division by zero
-            },
-            3 => TaskType::Multiply {
-                a: rng.gen_range(1..100),
-                b: rng.gen_range(1..100),
-            },
-            4 => TaskType::Factorial {
-                n: rng.gen_range(0..20),
-            },
-            5 => TaskType::PrimeCheck {
-                n: rng.gen_range(1..100),
-            },
-            6 => TaskType::ModuloExponentiation {
-                base: rng.gen_range(2..20),
-                exponent: rng.gen_range(2..10),
-                modulus: rng.gen_range(1..50) + 1, // Ensure non-zero modulus
-            },
-            _ => unreachable!(), // Should never happen given the 0..7 range
-        };
-        tasks.push(task);
-    }
-    tasks
+/// * `path` - The task graph file.
+/// * `options` - The thread count, task settings, repetitions and which summaries to print.
+fn run_graph(path: &Path, options: &Options) {
+    let graph = match TaskGraph::load(path) {
+        Ok(graph) => graph,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let (thread_count, settings, config) = (options.thread_counts[0], &options.settings, &options.config);
+    println!("Loaded task graph of {} tasks from {}", graph.nodes().len(), path.display());
+
+    // One worker runs the graph in dependency order, which is the serial baseline
+    println!("\n--- Running the graph on one worker ---");
+    let serial = benchmark(config, || execute_dag(&graph, 1, settings.clone()));
+    println!("\n--- Running the graph on {} workers ---", thread_count);
+    let concurrent = benchmark(config, || execute_dag(&graph, thread_count, settings.clone()));
+    print!("{}", summary::verification(&serial.last, &concurrent.last, "DAG executor"));
+
+    print!("{}", summary::timings(&serial.stats, &[("DAG executor", &concurrent.stats)]));
+    print!("{}", summary::failures("Serial", &serial.last));
+    print!("{}", summary::failures("DAG executor", &concurrent.last));
+    print!("{}", summary::workers("DAG executor", &concurrent.last));
+    // Label spans with the values each task actually ran with, not the graph's placeholders
+    let tasks = graph.bound_tasks(&concurrent.last.outputs);
+    if let Some(path) = &options.trace_path {
+        write_trace(path, &tasks, &[("Serial", &serial.last), ("DAG executor", &concurrent.last)], true);
+    }
+    if options.latency {
+        let priorities = graph.priorities(&settings.priorities);
+        print!("{}", LatencyReport::from_execution(&tasks, &serial.last, &priorities).display("Serial"));
+        let latency = LatencyReport::from_execution(&tasks, &concurrent.last, &priorities);
+        print!("{}", latency.display("DAG executor"));
+    }
 }
 
-/// Executes a list of tasks one at a time in serial order,
-/// measuring the total time taken to complete all tasks.
+/// Writes a Chrome trace of `runs` to `path`, reporting the outcome on stdout only when
+/// `text` output was requested; errors always go to stderr.
+fn write_trace(path: &Path, tasks: &[TaskType], runs: &[(&str, &ExecutionResult)], text: bool) {
+    match trace::save_trace(path, tasks, runs) {
+        Ok(()) if text => println!("\nWrote trace of {} run(s) to {}", runs.len(), path.display()),
+        Ok(()) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Compares a run with the latest comparable run stored in the history file and prints
+/// the outcome for every mode.
 ///
 /// # Arguments
-/// * `tasks` - A reference to a vector of `TaskType` values to be executed.
-/// * `simulate_load` - If `true`, introduces a brief delay before each task runs.
+/// * `path` - The history file holding the baseline.
+/// * `current` - The run that was just measured.
 ///
 /// # Returns
-/// A `Duration` representing the total elapsed time to run all tasks serially.
-fn execute_serially(tasks: &Vec<TaskType>, simulate_load: bool) -> Duration {
-    let start = Instant::now();
-    for task in tasks {
-        if simulate_load {
-            std::thread::sleep(Duration::from_micros(100));
+/// `true` if any mode regressed significantly against the baseline, or if no mode had
+/// enough samples to tell.
+fn compare_with_history(path: &Path, current: HistoryEntry) -> bool {
+    let history = match history::load(path) {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("{}\nRecord a baseline first with: bench --history {}", e, path.display());
+            process::exit(1);
+        }
+    };
+    match history::find_baseline(&history, &current) {
+        Some(baseline) => {
+            let comparisons = history::compare(&baseline.report, &current.report);
+            print!("{}", history::display_comparison(baseline, &comparisons));
+            history::has_regression(&comparisons) || history::is_inconclusive(&comparisons)
         }
-        if let Err(e) = task.run(simulate_load) {
-            eprintln!("Task failed: {}", e);
+        None => {
+            eprintln!(
+                "No run in {} with at least 2 samples per mode matches this machine, workload, mode, \
+                 thread count and settings; record one with bench --history {}.",
+                path.display(),
+                path.display()
+            );
+            process::exit(1);
         }
     }
-    start.elapsed()
 }
 
-/// Executes a list of tasks concurrently using multiple threads and returns the total duration.
+/// Benchmarks serial execution and each executor at a single thread count,
+/// then prints timing statistics and failure summaries for every mode.
 ///
-/// Tasks are distributed among threads by having each thread pop from a shared task queue
-/// protected by a mutex. Threads continue pulling tasks until the queue is empty.
+/// With `--format json` or `--format csv` only the machine-readable report is printed.
 ///
 /// # Arguments
-/// * `tasks` - A slice of `TaskType` elements to be executed.
-/// * `thread_count` - Number of threads to spawn for concurrent execution.
-/// * `simulate_load` - If `true`, introduces a fixed artificial delay before each task is run.
+/// * `tasks` - The batch every mode runs.
+/// * `options` - The executors to compare, their thread count, simulated load, repetitions,
+///   and which summaries and reports to produce.
 ///
 /// # Returns
-/// A `Duration` representing the total elapsed time to execute all tasks concurrently.
-pub fn execute_concurrently(tasks: &[TaskType], thread_count: u32, simulate_load: bool) -> Duration {
-    
-    // Wrap the task queue in Arc<Mutex<...>> to allow shared, synchronized access across threads.
-    let queue = Arc::new(Mutex::new(VecDeque::from(tasks.to_vec())));
-    let mut handles = Vec::new();
-
-    let start_time = Instant::now();
-
-    // Launch the specified number of worker threads
-    for _ in 0..thread_count {
-        let task_queue = Arc::clone(&queue);
-
-        let handle = thread::spawn(move || {
-            loop {
-                 // Lock the queue and try to pop the next task
-                let maybe_task = {
-                    let mut queue_guard = task_queue.lock().unwrap();
-                    queue_guard.pop_front()
-                };
-
-                match maybe_task {
-                    Some(task) => {
-                        // Simulate load if enabled (optional delay before running the task)
-                        if simulate_load {
-                            std::thread::sleep(Duration::from_micros(100));
-                        }
-                        // Execute the task and log any errors
-                        if let Err(e) = task.run(simulate_load) {
-                            eprintln!("Error executing task: {}", e);
-                        }
-                    },
-                    None => break, // Exit the loop if the queue is empty
-                }
-            }
-        });
-        // Store the handle so we can join it later
-        handles.push(handle);
+/// The machine-readable report of the run.
+fn compare_executors(tasks: &[TaskType], options: &Options) -> BenchmarkReport {
+    let (thread_count, settings, config) = (options.thread_counts[0], &options.settings, &options.config);
+    let text = options.format == OutputFormat::Text;
+    if text {
+        println!("Using {} threads for concurrent execution.", thread_count);
+        if !settings.priorities.is_equal() {
+            println!("Task priorities for the priority executor: {}", settings.priorities);
+        }
+        println!("\n--- Running tasks serially ---");
     }
 
-    // Wait for all threads to finish
-    for handle in handles {
-        handle.join().expect("Thread panicked during execution");
+    // Run the tasks serially and measure the execution time
+    let serial = benchmark(config, || execute_serially(tasks, settings.clone()));
+
+    // Run the tasks with each selected executor and measure the execution time
+    let mut concurrent = Vec::new();
+    for &executor in &options.executors {
+        if text {
+            println!("\n--- Running tasks concurrently ({}) ---", executor.name());
+        }
+        let measurement = measure_executor(tasks, executor, thread_count, settings.clone(), config);
+
+        // Every strategy runs the same batch, so its outputs must agree with the serial run
+        if text {
+            print!("{}", summary::verification(&serial.last, &measurement.last, executor.name()));
+        }
+        concurrent.push((executor, measurement));
     }
 
-    Instant::now() - start_time
+    let report_config = ReportConfig {
+        mode: settings.load.to_string(),
+        batch_size: tasks.len(),
+        workload: options.input_path.is_none().then(|| options.workload.clone()),
+        cpus: num_cpus::get(),
+        threads: thread_count,
+        warmup: config.warmup,
+        repetitions: config.repetitions,
+        settings: ReportSettings::new(settings, &options.executors),
+    };
+    let report = BenchmarkReport::from_measurements(report_config, &serial, &concurrent);
+    if let Some((path, format)) = &options.report {
+        match report.save(path, *format) {
+            Ok(()) if text => println!("Wrote benchmark report to {}", path.display()),
+            Ok(()) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    // Draw the serial and mutex queue runs as a timeline, one track per worker
+    if let Some(path) = &options.trace_path {
+        let mut runs = vec![("Serial", &serial.last)];
+        runs.extend(
+            concurrent
+                .iter()
+                .filter(|(executor, _)| *executor == Executor::MutexQueue)
+                .map(|(executor, measurement)| (executor.name(), &measurement.last)),
+        );
+        write_trace(path, tasks, &runs, text);
+    }
+    match options.format {
+        OutputFormat::Json => print!("{}", report.to_json()),
+        OutputFormat::Csv => print!("{}", report.to_csv()),
+        OutputFormat::Text => print_text_summary(tasks, options, &serial, &concurrent),
+    }
+    report
 }
 
-/// Compares the durations of serial and concurrent execution and prints a performance summary.
-///
-/// # Arguments
-/// * `serial` - Duration of the serial task execution.
-/// * `concurrent` - Duration of the concurrent task execution.
-fn compare_durations(serial: Duration, concurrent: Duration) {
-    println!("\n=== Execution Time Summary ===");
-    println!("Serial execution took:     {:.2?}", serial);
-    println!("Concurrent execution took: {:.2?}", concurrent);
-
-    // Compare the durations and report whether concurrency improved or hurt performance
-    if serial > concurrent {
-        let speedup = serial.as_secs_f64() / concurrent.as_secs_f64();
-        println!("Concurrent execution was {:.2}× faster.", speedup);
-    } else if concurrent > serial {
-        let slowdown = concurrent.as_secs_f64() / serial.as_secs_f64();
-        println!("⚠️  Serial execution was {:.2}× faster.", slowdown);
-    } else {
-        println!("Execution times were equal.");
+/// Prints the human-readable timing, failure, worker and latency summaries of a
+/// `compare_executors` run.
+fn print_text_summary(
+    tasks: &[TaskType],
+    options: &Options,
+    serial: &Measurement,
+    concurrent: &[(Executor, Measurement)],
+) {
+    // Compare and summarize the timing statistics of every mode
+    let stats: Vec<_> = concurrent.iter().map(|(executor, measurement)| (executor.name(), &measurement.stats)).collect();
+    print!("{}", summary::timings(&serial.stats, &stats));
+
+    // Report failed tasks, grouped by the kind of error
+    print!("{}", summary::failures("Serial", &serial.last));
+    for (executor, Measurement { last, .. }) in concurrent {
+        print!("{}", summary::failures(executor.name(), last));
+    }
+    for (executor, Measurement { last, .. }) in concurrent {
+        print!("{}", summary::workers(executor.name(), last));
+    }
+
+    // Break the last run of each mode down by task type and latency percentile
+    if options.latency {
+        let priorities = &options.settings.priorities;
+        print!("{}", LatencyReport::from_execution(tasks, &serial.last, priorities).display("Serial"));
+        for (executor, Measurement { last, .. }) in concurrent {
+            print!("{}", LatencyReport::from_execution(tasks, last, priorities).display(executor.name()));
+        }
     }
 }
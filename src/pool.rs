@@ -0,0 +1,169 @@
+use crate::cancel::CancellationToken;
+use crate::executor::{run_with_retries, ExecutionResult, TaskRecord, TaskSettings};
+use crate::task::{Task, TaskType};
+use crossbeam::channel::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A unit of work queued on the pool, tagged with its submission number, submission time
+/// and the cancellation token of the round it was submitted in.
+type Job = (usize, Box<dyn Task + Send>, Instant, CancellationToken);
+
+/// A fixed-size pool of long-lived worker threads.
+///
+/// Workers are spawned once in `new` and stay alive until the pool is dropped, so the same
+/// pool can run many batches without paying thread start-up cost each time. Work is
+/// submitted with `submit` and collected with `join`, which waits for everything submitted
+/// since the previous `join`. Everything submitted between two `join`s is one batch for the
+/// purpose of the batch timeout, which starts with the first submission.
+pub struct ThreadPool {
+    /// Sends jobs to the workers; `None` only while the pool is being dropped.
+    jobs: Option<Sender<Job>>,
+    /// Receives each job's output and timing, tagged with its submission number.
+    results: Receiver<TaskRecord>,
+    workers: Vec<JoinHandle<()>>,
+    /// Number of jobs submitted since the last `join`.
+    pending: usize,
+    /// Deadline of each round of submissions, counted from its first submission.
+    batch_timeout: Option<Duration>,
+    /// Token shared by the jobs submitted since the last `join`.
+    batch: CancellationToken,
+}
+
+impl ThreadPool {
+    /// Spawns `size` worker threads that wait for submitted tasks.
+    ///
+    /// # Arguments
+    /// * `size` - Number of worker threads to keep alive (at least 1).
+    /// * `settings` - Simulated load and timeouts applied to every task the pool runs.
+    pub fn new(size: u32, settings: TaskSettings) -> ThreadPool {
+        let (job_sender, job_receiver) = channel::unbounded::<Job>();
+        let (result_sender, results) = channel::unbounded();
+
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                let settings = settings.clone();
+                thread::spawn(move || {
+                    // Runs until the pool drops its sender and the queue is drained
+                    for (index, task, queued_at, batch) in job_receiver.iter() {
+                        let (result, timing) = run_with_retries(task.as_ref(), &settings, &batch, queued_at);
+                        if result_sender.send((index, result, timing)).is_err() {
+                            break; // The pool is gone, nobody is waiting for results
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        ThreadPool {
+            jobs: Some(job_sender),
+            results,
+            workers,
+            pending: 0,
+            batch_timeout: settings.batch_timeout,
+            batch: CancellationToken::new(),
+        }
+    }
+
+    /// Queues a task to be run by the next free worker.
+    pub fn submit(&mut self, task: Box<dyn Task + Send>) {
+        let jobs = self.jobs.as_ref().expect("Thread pool is shutting down");
+        if self.pending == 0 {
+            self.batch = CancellationToken::with_timeout(self.batch_timeout);
+        }
+        jobs.send((self.pending, task, Instant::now(), self.batch.clone())).expect("Thread pool workers exited");
+        self.pending += 1;
+    }
+
+    /// Waits for every task submitted since the last `join` to finish.
+    ///
+    /// # Returns
+    /// Each task's submission number, output and timing, in submission order.
+    pub fn join(&mut self) -> Vec<TaskRecord> {
+        let mut records: Vec<TaskRecord> = (0..self.pending)
+            .map(|_| self.results.recv().expect("Thread pool worker panicked"))
+            .collect();
+        self.pending = 0;
+        records.sort_by_key(|(index, _, _)| *index);
+        records
+    }
+
+    /// Runs a whole batch on the pool and times it, reusing the existing workers.
+    ///
+    /// # Arguments
+    /// * `tasks` - A slice of `TaskType` elements to be executed.
+    ///
+    /// # Returns
+    /// An `ExecutionResult` with the elapsed time and the output of every task.
+    pub fn execute(&mut self, tasks: &[TaskType]) -> ExecutionResult {
+        let start_time = Instant::now();
+        for task in tasks {
+            self.submit(Box::new(task.clone()));
+        }
+        let records = self.join();
+        ExecutionResult::from_records(start_time.elapsed(), records)
+    }
+}
+
+impl Drop for ThreadPool {
+    /// Disconnects the job queue and waits for every worker to exit.
+    fn drop(&mut self) {
+        drop(self.jobs.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::CancellationToken;
+    use crate::load::LoadModel;
+    use crate::task::{TaskError, TaskOutput};
+
+    /// A task that always panics, standing in for a bug in task code.
+    struct Panicking;
+
+    impl Task for Panicking {
+        fn run(&self, _: &LoadModel, _: &CancellationToken) -> Result<TaskOutput, TaskError> {
+            panic!("task bug")
+        }
+    }
+
+    #[test]
+    fn test_pool_reused_across_rounds() {
+        let mut pool = ThreadPool::new(3, TaskSettings::DEFAULT);
+
+        for round in 0..5 {
+            for n in 0..20 {
+                pool.submit(Box::new(TaskType::Compute { a: round, b: n }));
+            }
+            let expected: Vec<_> = (0..20).map(|n| Ok(TaskOutput::Integer((round + n) as i64))).collect();
+            let outputs: Vec<_> = pool.join().into_iter().map(|(_, result, _)| result).collect();
+            assert_eq!(outputs, expected);
+        }
+    }
+
+    #[test]
+    fn test_join_without_submissions() {
+        let mut pool = ThreadPool::new(2, TaskSettings::DEFAULT);
+        assert!(pool.join().is_empty());
+    }
+
+    #[test]
+    fn test_panicking_task_does_not_kill_worker() {
+        crate::executor::install_quiet_panic_hook();
+        // With one worker, the later tasks only finish if it survived the panic
+        let mut pool = ThreadPool::new(1, TaskSettings::DEFAULT);
+        pool.submit(Box::new(Panicking));
+        pool.submit(Box::new(TaskType::Compute { a: 1, b: 2 }));
+        let outputs: Vec<_> = pool.join().into_iter().map(|(_, result, _)| result).collect();
+        assert_eq!(outputs, vec![Err(TaskError::Panicked("task bug".into())), Ok(TaskOutput::Integer(3))]);
+
+        pool.submit(Box::new(Panicking));
+        assert_eq!(pool.join().len(), 1);
+    }
+}
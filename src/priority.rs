@@ -0,0 +1,242 @@
+//! Task priorities and the multi-level queue the priority executor schedules from.
+//!
+//! Every task kind is assigned a `Priority` by a `Priorities` map, and individual tasks of a
+//! batch, such as those in a task file, can set their own. A `MultiLevelQueue` keeps
+//! one FIFO per priority and serves the highest level first, but ages waiting entries up one
+//! level for every `aging` interval they have waited, so low-priority work is never starved.
+
+use crate::task::TaskType;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How urgently a task should be run, from least to most urgent.
+///
+/// Serialized as its `label`, e.g. `"high"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Priority {
+    /// Every priority, from least to most urgent.
+    pub const ALL: [Priority; 3] = [Priority::Low, Priority::Normal, Priority::High];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+        }
+    }
+
+    /// Parses a priority `label`.
+    pub fn parse(raw: &str) -> Result<Priority, String> {
+        Priority::ALL
+            .into_iter()
+            .find(|priority| priority.label() == raw)
+            .ok_or_else(|| format!("Unknown priority '{}'; expected low, normal or high.", raw))
+    }
+
+    fn level(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// The priority of every task in a batch: the priority of its kind, unless the task set
+/// its own.
+///
+/// Priorities set by individual tasks are stored by the task's position in the batch, so
+/// they only apply to the batch they were read with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Priorities {
+    /// The priority of each task kind, indexed by `TaskType::index`.
+    levels: [Priority; TaskType::ALL_KEYS.len()],
+    /// The priority each task of the batch set for itself, if any task did.
+    tasks: Option<Arc<[Option<Priority>]>>,
+}
+
+impl Priorities {
+    /// Every task kind at normal priority.
+    pub const EQUAL: Priorities = Priorities { levels: [Priority::Normal; TaskType::ALL_KEYS.len()], tasks: None };
+
+    /// These priorities, with each task of the batch at the priority it set for itself.
+    ///
+    /// # Arguments
+    /// * `tasks` - The priority each task set, in batch order; `None` for tasks that take
+    ///   their kind's priority.
+    pub fn with_task_priorities(&self, tasks: Vec<Option<Priority>>) -> Priorities {
+        let tasks = tasks.iter().any(Option::is_some).then(|| tasks.into());
+        Priorities { levels: self.levels, tasks }
+    }
+
+    /// The priority the task at `index` of the batch set for itself, if any.
+    pub fn of_task(&self, index: usize) -> Option<Priority> {
+        self.tasks.as_ref().and_then(|tasks| tasks.get(index).copied().flatten())
+    }
+
+    /// The priority of the task at `index` of the batch: its own if it set one, otherwise
+    /// its kind's.
+    pub fn of(&self, index: usize, task: &TaskType) -> Priority {
+        self.of_task(index).unwrap_or(self.levels[task.index()])
+    }
+
+    /// Whether every task kind has the same priority and no task set its own.
+    pub fn is_equal(&self) -> bool {
+        self.tasks.is_none() && self.levels.iter().all(|level| *level == self.levels[0])
+    }
+
+    /// Parses a `--priority` value such as `compute:high,prime_check:low`. Task kinds that
+    /// are not listed keep normal priority.
+    pub fn parse(raw: &str) -> Result<Priorities, String> {
+        let mut priorities = Priorities::EQUAL;
+        for entry in raw.split(',') {
+            let (key, level) = entry
+                .trim()
+                .split_once(':')
+                .ok_or_else(|| format!("Expected task:priority, got '{}'.", entry.trim()))?;
+            let slot = TaskType::ALL_KEYS
+                .iter()
+                .position(|known| *known == key)
+                .ok_or_else(|| format!("Unknown task variant '{}'.", key))?;
+            priorities.levels[slot] = Priority::parse(level)?;
+        }
+        Ok(priorities)
+    }
+}
+
+impl fmt::Display for Priorities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.levels.iter().all(|level| *level == self.levels[0]) {
+            write!(f, "all {}", self.levels[0].label())?;
+        } else {
+            let levels: Vec<String> = TaskType::ALL_KEYS
+                .iter()
+                .zip(&self.levels)
+                .filter(|(_, level)| **level != Priority::Normal)
+                .map(|(key, level)| format!("{} {}", key, level.label()))
+                .collect();
+            write!(f, "{}", levels.join(", "))?;
+        }
+        match &self.tasks {
+            Some(tasks) => write!(f, "; {} task(s) set their own", tasks.iter().flatten().count()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// One FIFO per priority, served highest level first with aging.
+///
+/// An entry's effective level is its priority plus one for every full `aging` interval it
+/// has waited, capped at the highest level. `pop` serves the head with the highest effective
+/// level, breaking ties in favour of the entry pushed first. Only the head of each FIFO can
+/// have waited the longest in its level, so a pop looks at no more than three entries.
+pub struct MultiLevelQueue<T> {
+    levels: [VecDeque<(T, Instant, u64)>; 3],
+    aging: Duration,
+    /// Incremented on every push, so ties go to the entry that was pushed first.
+    pushed: u64,
+}
+
+impl<T> MultiLevelQueue<T> {
+    /// An empty queue that ages entries up one level every `aging`; a zero interval disables aging.
+    pub fn new(aging: Duration) -> MultiLevelQueue<T> {
+        MultiLevelQueue { levels: Default::default(), aging, pushed: 0 }
+    }
+
+    pub fn push(&mut self, item: T, priority: Priority) {
+        self.levels[priority.level()].push_back((item, Instant::now(), self.pushed));
+        self.pushed += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.iter().all(VecDeque::is_empty)
+    }
+
+    /// Removes the entry to run next, along with the priority it was pushed with.
+    pub fn pop(&mut self) -> Option<(T, Priority)> {
+        let now = Instant::now();
+        let top = Priority::High.level();
+        let (level, _) = self
+            .levels
+            .iter()
+            .enumerate()
+            .filter_map(|(level, queue)| {
+                let (_, pushed_at, order) = queue.front()?;
+                let boost = match self.aging.as_nanos() {
+                    0 => 0,
+                    aging => ((now - *pushed_at).as_nanos() / aging).min(top as u128) as usize,
+                };
+                Some((level, ((level + boost).min(top), std::cmp::Reverse(*order))))
+            })
+            .max_by_key(|(_, rank)| *rank)?;
+        let (item, _, _) = self.levels[level].pop_front()?;
+        Some((item, Priority::ALL[level]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_parse_priorities() {
+        let priorities = Priorities::parse("compute:high,prime_check:low").unwrap();
+        assert_eq!(priorities.of(0, &TaskType::Compute { a: 1, b: 2 }), Priority::High);
+        assert_eq!(priorities.of(0, &TaskType::PrimeCheck { n: 7 }), Priority::Low);
+        assert_eq!(priorities.of(0, &TaskType::Fibonacci { n: 7 }), Priority::Normal);
+        assert_eq!(priorities.to_string(), "compute high, prime_check low");
+        assert_eq!(Priorities::EQUAL.to_string(), "all normal");
+        for bad in ["compute", "compute:urgent", "sorting:high"] {
+            assert!(Priorities::parse(bad).is_err(), "{} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_tasks_set_their_own_priority() {
+        let priorities = Priorities::parse("compute:high").unwrap();
+        assert_eq!(priorities.with_task_priorities(vec![None, None]), priorities);
+
+        let priorities = priorities.with_task_priorities(vec![None, Some(Priority::Low)]);
+        let compute = TaskType::Compute { a: 1, b: 2 };
+        assert_eq!(priorities.of(0, &compute), Priority::High);
+        assert_eq!(priorities.of(1, &compute), Priority::Low);
+        // Tasks past the end of the list take their kind's priority
+        assert_eq!(priorities.of(2, &compute), Priority::High);
+        assert_eq!(priorities.of_task(1), Some(Priority::Low));
+        assert_eq!(priorities.to_string(), "compute high; 1 task(s) set their own");
+        assert!(!Priorities::EQUAL.with_task_priorities(vec![Some(Priority::Normal)]).is_equal());
+    }
+
+    #[test]
+    fn test_highest_level_first() {
+        let mut queue = MultiLevelQueue::new(Duration::ZERO);
+        for (item, priority) in [(0, Priority::Low), (1, Priority::High), (2, Priority::Normal), (3, Priority::High)] {
+            queue.push(item, priority);
+        }
+        let order: Vec<_> = iter_pop(&mut queue).collect();
+        assert_eq!(order, vec![(1, Priority::High), (3, Priority::High), (2, Priority::Normal), (0, Priority::Low)]);
+    }
+
+    #[test]
+    fn test_aging_prevents_starvation() {
+        let mut queue = MultiLevelQueue::new(Duration::from_millis(5));
+        queue.push("old low", Priority::Low);
+        thread::sleep(Duration::from_millis(12));
+        queue.push("new high", Priority::High);
+        // Two aging intervals lift the low entry to the top level, where it is older
+        assert_eq!(queue.pop(), Some(("old low", Priority::Low)));
+        assert_eq!(queue.pop(), Some(("new high", Priority::High)));
+        assert_eq!(queue.pop(), None);
+    }
+
+    fn iter_pop<T>(queue: &mut MultiLevelQueue<T>) -> impl Iterator<Item = (T, Priority)> + '_ {
+        std::iter::from_fn(move || queue.pop())
+    }
+}
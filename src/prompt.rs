@@ -0,0 +1,214 @@
+//! Interactive configuration through stdin prompts, used when no arguments are given.
+
+use crate::cli::{self, Command, Options};
+use cs354_rust::{BenchConfig, Executor, LoadModel, OutputFormat, TaskSettings, WorkloadManifest, WorkloadSpec};
+use std::io;
+
+/// Collects a configuration through stdin prompts.
+///
+/// Prompts the user to choose between two execution modes:
+/// 1. Default (concurrent execution using a mutex-protected queue)
+/// 2. Simulated task load (adds delay to simulate real-world task latency)
+///
+/// The user is then asked to specify:
+/// - The concurrent executor to benchmark (or all of them side by side)
+/// - The number of tasks to generate
+/// - The number of threads to use for concurrent execution (validated against CPU count)
+/// - The number of warmup and measured repetitions
+/// - The RNG seed used to generate the batch
+///
+/// # Returns
+/// `Options` equivalent to the ones the `bench` or `sweep` command would produce.
+pub fn prompt_for_options() -> Options {
+    println!("Choose mode:");
+    println!("[1] Default (Mutex-based concurrency)");
+    println!("[2] Simulate realistic task load");
+
+    // Loop until the user enters a valid mode (1 or 2)
+    let mode = loop {
+        let m = prompt_for_u32("Enter choice:");
+        if m == 1 || m == 2 {
+            break m;
+        } else {
+            println!("Invalid mode. Please enter 1 or 2.");
+        }
+    };
+    
+    // Enable simulated load delay if user selects mode 2.
+    let load = if mode == 2 { LoadModel::SIMULATED } else { LoadModel::NONE };
+
+    println!("Choose benchmark:");
+    println!("[1] Compare executors at one thread count");
+    println!("[2] Sweep thread counts and estimate scaling");
+    let command = loop {
+        match prompt_for_u32("Enter choice:") {
+            1 => break Command::Bench,
+            2 => break Command::Sweep,
+            _ => println!("Invalid benchmark. Please enter 1 or 2."),
+        }
+    };
+
+    let executors = prompt_for_executors();
+
+    // Prompt for how many tasks to generate
+    let batch_size = prompt_for_u32("Enter number of tasks to generate:");
+    
+    // Get number of threads from user input.
+    let max_threads = num_cpus::get() as u32;
+    let thread_counts = if command == Command::Sweep {
+        prompt_for_thread_counts(max_threads)
+    } else {
+        let count = loop {
+            let count = prompt_for_u32(&format!("Enter number of threads [1-{}]:", max_threads));
+            if count > 0 && count <= max_threads {
+                break count;
+            } else {
+                println!("Please enter a number between 1 and {}.", max_threads);
+            }
+        };
+        vec![count]
+    };
+
+    // Prompt for how many times to repeat each measurement
+    let config = BenchConfig {
+        warmup: prompt_for_number("Enter number of warmup iterations:", 0),
+        repetitions: prompt_for_u32("Enter number of measured repetitions:"),
+    };
+
+    let seed = prompt_for_seed();
+
+    Options {
+        command,
+        settings: TaskSettings { load, ..TaskSettings::DEFAULT },
+        workload: WorkloadManifest { seed, batch_size, spec: WorkloadSpec::uniform() },
+        manifest_path: None,
+        input_path: None,
+        graph_path: None,
+        tasks_path: None,
+        format: OutputFormat::Text,
+        report: None,
+        history_path: None,
+        trace_path: None,
+        latency: false,
+        thread_counts,
+        executors,
+        config,
+    }
+}
+
+/// Asks for the seed used to generate the batch.
+///
+/// # Returns
+/// The entered seed, or `cli::DEFAULT_SEED` if left blank.
+fn prompt_for_seed() -> u64 {
+    loop {
+        println!("Enter RNG seed (blank for {}):", cli::DEFAULT_SEED);
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() {
+            println!("Failed to read input. Try again.");
+            continue;
+        }
+        if input.trim().is_empty() {
+            return cli::DEFAULT_SEED;
+        }
+        match input.trim().parse::<u64>() {
+            Ok(seed) => return seed,
+            Err(_) => println!("Invalid seed. Enter a non-negative integer."),
+        }
+    }
+}
+
+/// Asks which thread counts a sweep should measure.
+///
+/// # Arguments
+/// * `max_threads` - Number of logical CPUs, used for the default range.
+///
+/// # Returns
+/// The thread counts entered as a comma-separated list, or `1..=max_threads` if left blank.
+fn prompt_for_thread_counts(max_threads: u32) -> Vec<u32> {
+    loop {
+        println!("Enter thread counts to sweep, separated by commas (blank for 1-{}):", max_threads);
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() {
+            println!("Failed to read input. Try again.");
+            continue;
+        }
+        if input.trim().is_empty() {
+            return (1..=max_threads).collect();
+        }
+        match cli::parse_thread_counts(&input) {
+            Some(counts) => return counts,
+            None => println!("Invalid list. Enter positive numbers such as 1,2,4,8."),
+        }
+    }
+}
+
+/// Asks which concurrent executor to benchmark.
+///
+/// # Returns
+/// The single selected executor, or every executor when the user picks "all".
+fn prompt_for_executors() -> Vec<Executor> {
+    println!("Choose executor:");
+    for (i, executor) in Executor::ALL.iter().enumerate() {
+        println!("[{}] {}", i + 1, executor.name());
+    }
+    let all_choice = Executor::ALL.len() as u32 + 1;
+    println!("[{}] All (side by side)", all_choice);
+
+    let mut executors = loop {
+        let choice = prompt_for_u32("Enter choice:");
+        if choice == all_choice {
+            break Executor::ALL.to_vec();
+        } else if choice < all_choice {
+            break vec![Executor::ALL[choice as usize - 1]];
+        } else {
+            println!("Invalid executor. Please enter a number between 1 and {}.", all_choice);
+        }
+    };
+
+    // The atomic-index executor can claim several tasks per atomic operation
+    if executors.iter().any(|executor| matches!(executor, Executor::AtomicIndex { .. })) {
+        let chunk_size = prompt_for_u32("Enter atomic index chunk size (tasks claimed at once):") as usize;
+        for executor in &mut executors {
+            if let Executor::AtomicIndex { chunk_size: size } = executor {
+                *size = chunk_size;
+            }
+        }
+    }
+    executors
+}
+
+/// Prompts the user for a positive integer and validates the input.
+///
+/// # Arguments
+/// * `prompt` - A string prompt to display to the user.
+///
+/// # Returns
+/// A validated, non-zero `u32` entered by the user.
+fn prompt_for_u32(prompt: &str) -> u32 {
+    prompt_for_number(prompt, 1)
+}
+
+/// Prompts the user for an integer of at least `min` and validates the input.
+///
+/// # Arguments
+/// * `prompt` - A string prompt to display to the user.
+/// * `min` - The smallest accepted value.
+///
+/// # Returns
+/// A validated `u32` no smaller than `min` entered by the user.
+fn prompt_for_number(prompt: &str, min: u32) -> u32 {
+    loop {
+        println!("{}", prompt);
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() {
+            println!("Failed to read input. Try again.");
+            continue;
+        }
+        match input.trim().parse::<u32>() {
+            Ok(num) if num >= min => return num, // Valid number >= min
+            Ok(_) => println!("Please enter a number of at least {}.", min), // Below the minimum
+            Err(_) => println!("Invalid number. Try again."), // Not a number
+        }
+    }
+}
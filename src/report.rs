@@ -0,0 +1,421 @@
+//! Machine-readable benchmark results.
+//!
+//! A `BenchmarkReport` captures how a run was configured and how every mode performed,
+//! and can be written as a JSON document or appended to a CSV file as one row per mode.
+
+use crate::bench::{Measurement, Stats};
+use crate::executor::{ExecutionResult, Executor, TaskSettings};
+use crate::retry::RetryPolicy;
+use crate::taskfile::TaskFileFormat;
+use crate::workload::WorkloadManifest;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Header line of CSV reports; each row after it describes one mode of one run.
+const CSV_HEADER: &str = "timestamp,mode,batch_size,seed,workload,cpus,threads,warmup,repetitions,\
+timeout,batch_timeout,retry,priorities,chunk_size,aging,executor,mean_ns,median_ns,std_dev_ns,min_ns,max_ns,ci95_low_ns,ci95_high_ns,speedup,errors,mismatches,retries,panics";
+
+/// How results are written to stdout or a report file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable tables and summaries.
+    Text,
+    /// A `BenchmarkReport` as one JSON document.
+    Json,
+    /// A `BenchmarkReport` as CSV rows, one per mode.
+    Csv,
+}
+
+impl OutputFormat {
+    /// Parses a `--format` value.
+    pub fn parse(raw: &str) -> Result<OutputFormat, String> {
+        match raw {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!("Unknown format '{}'; expected text, json or csv.", other)),
+        }
+    }
+
+    /// Picks the report format from a file's extension, the same way task files do.
+    pub fn from_path(path: &Path) -> Result<OutputFormat, String> {
+        Ok(match TaskFileFormat::from_path(path)? {
+            TaskFileFormat::Json => OutputFormat::Json,
+            TaskFileFormat::Csv => OutputFormat::Csv,
+        })
+    }
+}
+
+/// How a benchmark run was set up.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReportConfig {
+    /// The simulated load added to every task, e.g. `none` or `fixed 100µs (sleep)`.
+    pub mode: String,
+    pub batch_size: usize,
+    /// The manifest that generated the batch; `None` when it was loaded from a task file.
+    pub workload: Option<WorkloadManifest>,
+    /// Logical CPUs on the machine that ran the benchmark.
+    pub cpus: usize,
+    /// Threads used by every concurrent executor.
+    pub threads: u32,
+    pub warmup: u32,
+    pub repetitions: u32,
+    /// Deadlines, retries and scheduling knobs; absent from reports written before they were recorded.
+    #[serde(default)]
+    pub settings: ReportSettings,
+}
+
+/// The task settings and executor tuning a run used, beyond the simulated load.
+///
+/// Each field is `None` when the default was used, so that reports written before these
+/// settings were recorded read back as default runs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportSettings {
+    /// Per-task timeout, e.g. `5ms`.
+    pub timeout: Option<String>,
+    pub batch_timeout: Option<String>,
+    /// The retry policy, e.g. `up to 3 attempts, 1ms backoff`.
+    pub retry: Option<String>,
+    /// Task priorities for the priority executor, e.g. `compute high, prime_check low`.
+    pub priorities: Option<String>,
+    /// Chunk size of the atomic executor, if it ran.
+    pub chunk_size: Option<usize>,
+    /// Aging interval of the priority executor, if it ran.
+    pub aging: Option<String>,
+}
+
+impl ReportSettings {
+    /// Describes `settings` and the tuning of the atomic and priority executors in `executors`.
+    pub fn new(settings: &TaskSettings, executors: &[Executor]) -> ReportSettings {
+        ReportSettings {
+            timeout: settings.timeout.map(|timeout| format!("{:?}", timeout)),
+            batch_timeout: settings.batch_timeout.map(|timeout| format!("{:?}", timeout)),
+            retry: (settings.retry != RetryPolicy::NONE).then(|| settings.retry.to_string()),
+            priorities: (!settings.priorities.is_equal()).then(|| settings.priorities.to_string()),
+            chunk_size: executors.iter().find_map(|executor| match executor {
+                Executor::AtomicIndex { chunk_size } => Some(*chunk_size),
+                _ => None,
+            }),
+            aging: executors.iter().find_map(|executor| match executor {
+                Executor::Priority { aging } => Some(format!("{:?}", aging)),
+                _ => None,
+            }),
+        }
+    }
+}
+
+/// How one mode (serial or one executor) performed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModeReport {
+    /// `serial` or the executor's `Executor::key`.
+    pub executor: String,
+    /// Every measured duration in nanoseconds, in the order it was recorded.
+    pub samples_ns: Vec<u64>,
+    pub mean_ns: u64,
+    pub median_ns: u64,
+    pub std_dev_ns: u64,
+    pub min_ns: u64,
+    pub max_ns: u64,
+    /// Bounds of the 95% confidence interval for the mean.
+    pub ci95_ns: (u64, u64),
+    /// Serial mean over this mode's mean; `1.0` for the serial mode itself.
+    pub speedup: f64,
+    /// Tasks that returned an error in the last measured run.
+    pub errors: usize,
+    /// Task outputs of the last measured run that differ from the serial run.
+    pub mismatches: usize,
+    /// Retries made by the retry policy in the last measured run.
+    #[serde(default)]
+    pub retries: usize,
+    /// Tasks that panicked in the last measured run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub panics: Vec<TaskPanic>,
+}
+
+/// A task that panicked instead of returning.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskPanic {
+    /// Position of the task in the batch.
+    pub index: usize,
+    /// The panic's payload message.
+    pub message: String,
+}
+
+/// The configuration and results of one benchmark run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// When the run finished, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub config: ReportConfig,
+    /// The serial mode first, then each executor in the order it ran.
+    pub results: Vec<ModeReport>,
+}
+
+impl ModeReport {
+    /// Summarizes one mode's statistics and last run against the serial baseline.
+    ///
+    /// # Arguments
+    /// * `executor` - `serial` or the executor's key.
+    /// * `stats` - Statistics over the mode's measured runs.
+    /// * `last` - The mode's last measured run.
+    /// * `serial` - The serial mode's statistics and last run, used for speedup and verification.
+    pub fn new(executor: &str, stats: &Stats, last: &ExecutionResult, serial: (&Stats, &ExecutionResult)) -> ModeReport {
+        let (serial_stats, serial_last) = serial;
+        ModeReport {
+            executor: executor.to_string(),
+            samples_ns: stats.samples.iter().map(|&sample| nanos(sample)).collect(),
+            mean_ns: nanos(stats.mean),
+            median_ns: nanos(stats.median),
+            std_dev_ns: nanos(stats.std_dev),
+            min_ns: nanos(stats.min),
+            max_ns: nanos(stats.max),
+            ci95_ns: (nanos(stats.ci95.0), nanos(stats.ci95.1)),
+            speedup: serial_stats.mean.as_secs_f64() / stats.mean.as_secs_f64(),
+            errors: last.failures_by_kind().values().sum(),
+            mismatches: last.mismatches(serial_last),
+            retries: last.retries().0,
+            panics: last
+                .panics()
+                .into_iter()
+                .map(|(index, message)| TaskPanic { index, message: message.to_string() })
+                .collect(),
+        }
+    }
+}
+
+impl BenchmarkReport {
+    /// Builds a report timestamped with the current time.
+    pub fn new(config: ReportConfig, results: Vec<ModeReport>) -> BenchmarkReport {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        BenchmarkReport { timestamp, config, results }
+    }
+
+    /// Builds a report from a serial measurement and one measurement per executor, such as
+    /// those taken by `benchmark` and `measure_executor`.
+    pub fn from_measurements(
+        config: ReportConfig,
+        serial: &Measurement,
+        concurrent: &[(Executor, Measurement)],
+    ) -> BenchmarkReport {
+        let baseline = (&serial.stats, &serial.last);
+        let mut results = vec![ModeReport::new("serial", &serial.stats, &serial.last, baseline)];
+        for (executor, measurement) in concurrent {
+            results.push(ModeReport::new(executor.key(), &measurement.stats, &measurement.last, baseline));
+        }
+        BenchmarkReport::new(config, results)
+    }
+
+    /// The report as a pretty-printed JSON document.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Reports are always serializable") + "\n"
+    }
+
+    /// The report as CSV rows, one per mode, without a header.
+    pub fn to_csv_rows(&self) -> String {
+        let config = &self.config;
+        let (seed, workload) = match &config.workload {
+            Some(manifest) => (manifest.seed.to_string(), manifest.spec.to_string()),
+            None => (String::new(), "task file".to_string()),
+        };
+        // Settings left at their defaults are empty cells
+        let settings = &config.settings;
+        let optional = |value: &Option<String>| value.as_deref().map_or(String::new(), csv_field);
+        let mut text = String::new();
+        for result in &self.results {
+            let fields = [
+                self.timestamp.to_string(),
+                csv_field(&config.mode),
+                config.batch_size.to_string(),
+                seed.clone(),
+                csv_field(&workload),
+                config.cpus.to_string(),
+                config.threads.to_string(),
+                config.warmup.to_string(),
+                config.repetitions.to_string(),
+                optional(&settings.timeout),
+                optional(&settings.batch_timeout),
+                optional(&settings.retry),
+                optional(&settings.priorities),
+                settings.chunk_size.map_or(String::new(), |chunk_size| chunk_size.to_string()),
+                optional(&settings.aging),
+                result.executor.clone(),
+                result.mean_ns.to_string(),
+                result.median_ns.to_string(),
+                result.std_dev_ns.to_string(),
+                result.min_ns.to_string(),
+                result.max_ns.to_string(),
+                result.ci95_ns.0.to_string(),
+                result.ci95_ns.1.to_string(),
+                format!("{:.4}", result.speedup),
+                result.errors.to_string(),
+                result.mismatches.to_string(),
+                result.retries.to_string(),
+                result.panics.len().to_string(),
+            ];
+            text.push_str(&fields.join(","));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The report as CSV with a header line.
+    pub fn to_csv(&self) -> String {
+        format!("{}\n{}", CSV_HEADER, self.to_csv_rows())
+    }
+
+    /// Writes the report to `path` in `format`.
+    ///
+    /// JSON replaces any existing file. CSV appends one row per mode, writing the header
+    /// first if the file is new or empty, so repeated runs accumulate in one file. Appending
+    /// to a CSV file whose header has different columns is an error.
+    pub fn save(&self, path: &Path, format: OutputFormat) -> Result<(), String> {
+        let write_error = |e: std::io::Error| format!("Failed to write report '{}': {}", path.display(), e);
+        match format {
+            OutputFormat::Csv => {
+                let is_empty = fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+                if !is_empty {
+                    let existing = fs::read_to_string(path).map_err(write_error)?;
+                    if existing.lines().next() != Some(CSV_HEADER) {
+                        return Err(format!(
+                            "Report '{}' has different CSV columns; write to a new file instead.",
+                            path.display()
+                        ));
+                    }
+                }
+                let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(write_error)?;
+                let text = if is_empty { self.to_csv() } else { self.to_csv_rows() };
+                file.write_all(text.as_bytes()).map_err(write_error)
+            }
+            _ => fs::write(path, self.to_json()).map_err(write_error),
+        }
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, quote or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{execute_serially, TaskSettings};
+    use crate::load::LoadModel;
+    use crate::priority::Priorities;
+    use crate::task::TaskType;
+    use crate::workload::WorkloadSpec;
+
+    fn sample_report() -> BenchmarkReport {
+        let tasks = vec![TaskType::Compute { a: 1, b: 2 }, TaskType::Divide { numerator: 1, denominator: 0 }];
+        let last = execute_serially(&tasks, TaskSettings::DEFAULT);
+        let serial = Stats::from_samples(vec![Duration::from_micros(40), Duration::from_micros(60)]);
+        let faster = Stats::from_samples(vec![Duration::from_micros(20), Duration::from_micros(30)]);
+        let config = ReportConfig {
+            mode: LoadModel::NONE.to_string(),
+            batch_size: tasks.len(),
+            workload: Some(WorkloadManifest { seed: 7, batch_size: 2, spec: WorkloadSpec::parse_mix("compute:3,divide:1").unwrap() }),
+            cpus: 4,
+            threads: 2,
+            warmup: 0,
+            repetitions: 2,
+            settings: ReportSettings::default(),
+        };
+        BenchmarkReport::new(config, vec![
+            ModeReport::new("serial", &serial, &last, (&serial, &last)),
+            ModeReport::new("mutex", &faster, &last, (&serial, &last)),
+        ])
+    }
+
+    #[test]
+    fn test_mode_report() {
+        let report = sample_report();
+        let mutex = &report.results[1];
+        assert_eq!(mutex.mean_ns, 25_000);
+        assert_eq!(mutex.samples_ns, vec![20_000, 30_000]);
+        assert!((mutex.speedup - 2.0).abs() < 1e-9);
+        assert_eq!((mutex.errors, mutex.mismatches), (1, 0));
+        assert!((report.results[0].speedup - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let report = sample_report();
+        let parsed: BenchmarkReport = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(parsed, report);
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let csv = sample_report().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[2].contains(",7,\"75% compute, 25% divide\",4,2,0,2,,,,,,,mutex,25000,"));
+        assert!(lines[2].ends_with(",2.0000,1,0,0,0"));
+    }
+
+    #[test]
+    fn test_csv_rows_with_flaky_load() {
+        let mut report = sample_report();
+        report.config.mode = LoadModel::NONE.with_failure_rate(0.1).to_string();
+        report.results[1].retries = 3;
+        let settings = TaskSettings {
+            retry: RetryPolicy::parse("3:fixed:1ms").unwrap(),
+            priorities: Priorities::parse("compute:high,divide:low").unwrap(),
+            ..TaskSettings::DEFAULT
+        };
+        report.config.settings = ReportSettings::new(&settings, &[Executor::AtomicIndex { chunk_size: 64 }]);
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[2].contains(",\"none, 10% transient failures\",2,7,"), "{}", lines[2]);
+        let retry = csv_field(&settings.retry.to_string());
+        let priorities = csv_field(&settings.priorities.to_string());
+        assert!(lines[2].contains(&format!(",2,,,{},{},64,,mutex,", retry, priorities)), "{}", lines[2]);
+        assert!(lines[2].ends_with(",2.0000,1,0,3,0"));
+        for line in lines {
+            assert_eq!(field_count(line), CSV_HEADER.split(',').count(), "{}", line);
+        }
+    }
+
+    /// Number of fields in a CSV line, not counting commas inside quotes.
+    fn field_count(line: &str) -> usize {
+        let mut quoted = false;
+        1 + line
+            .chars()
+            .filter(|c| {
+                if *c == '"' {
+                    quoted = !quoted;
+                }
+                *c == ',' && !quoted
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_csv_save_appends() {
+        let path = std::env::temp_dir().join(format!("report-{}.csv", std::process::id()));
+        let report = sample_report();
+        report.save(&path, OutputFormat::Csv).unwrap();
+        report.save(&path, OutputFormat::Csv).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(text.lines().count(), 5);
+        assert_eq!(text.matches("timestamp,").count(), 1);
+
+        // A file written with other columns is not appended to
+        fs::write(&path, "timestamp,mode\n1,none\n").unwrap();
+        assert!(report.save(&path, OutputFormat::Csv).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
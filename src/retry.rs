@@ -0,0 +1,133 @@
+//! Retrying tasks that fail with transient errors.
+//!
+//! A `RetryPolicy` decides whether a failed attempt is worth repeating and how long to back
+//! off first. Only errors that `TaskError::is_transient` classifies as transient are retried;
+//! deterministic failures such as a division by zero would fail the same way every time.
+
+use crate::load::parse_duration;
+use crate::task::TaskError;
+use std::fmt;
+use std::time::Duration;
+
+/// How long to wait before each retry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backoff {
+    /// The same delay before every retry.
+    Fixed(Duration),
+    /// `initial` before the first retry, doubling for each one after it, up to `max`.
+    Exponential { initial: Duration, max: Duration },
+}
+
+/// When and how often a failed task is attempted again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts allowed per task, including the first; `1` disables retries.
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+}
+
+impl RetryPolicy {
+    /// Every task is attempted exactly once.
+    pub const NONE: RetryPolicy = RetryPolicy { max_attempts: 1, backoff: Backoff::Fixed(Duration::ZERO) };
+
+    /// Whether a task that has failed `attempts` times, most recently with `error`, should be
+    /// attempted again.
+    pub fn should_retry(&self, error: &TaskError, attempts: u32) -> bool {
+        attempts < self.max_attempts && error.is_transient()
+    }
+
+    /// The delay before the next attempt of a task that has been attempted `attempts` times.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        match self.backoff {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => {
+                let factor = 1u32.checked_shl(attempts.saturating_sub(1)).unwrap_or(u32::MAX);
+                initial.checked_mul(factor).map_or(max, |delay| delay.min(max))
+            }
+        }
+    }
+
+    /// Parses a `--retry` value: `N` for up to N attempts without backoff, `N:fixed:D`, or
+    /// `N:exp:D` and `N:exp:D:MAX` for exponential backoff starting at D (capped at 1s
+    /// unless MAX is given).
+    pub fn parse(raw: &str) -> Result<RetryPolicy, String> {
+        let parts: Vec<&str> = raw.split(':').map(str::trim).collect();
+        let invalid = || {
+            format!(
+                "Invalid retry policy '{}'; expected N, N:fixed:D, N:exp:D or N:exp:D:MAX with N at least 1.",
+                raw
+            )
+        };
+        let max_attempts = parts[0].parse::<u32>().ok().filter(|&n| n > 0).ok_or_else(invalid)?;
+        let duration = |i: usize| parts.get(i).and_then(|part| parse_duration(part)).ok_or_else(invalid);
+        let backoff = match (parts.get(1).copied(), parts.len()) {
+            (None, _) => Backoff::Fixed(Duration::ZERO),
+            (Some("fixed"), 3) => Backoff::Fixed(duration(2)?),
+            (Some("exp"), 3) => Backoff::Exponential { initial: duration(2)?, max: Duration::from_secs(1) },
+            (Some("exp"), 4) => {
+                let (initial, max) = (duration(2)?, duration(3)?);
+                if initial > max {
+                    return Err(format!("Invalid retry policy '{}'; the initial backoff is above the maximum.", raw));
+                }
+                Backoff::Exponential { initial, max }
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(RetryPolicy { max_attempts, backoff })
+    }
+}
+
+impl fmt::Display for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.max_attempts == 1 {
+            return write!(f, "none");
+        }
+        write!(f, "up to {} attempts", self.max_attempts)?;
+        match self.backoff {
+            Backoff::Fixed(Duration::ZERO) => Ok(()),
+            Backoff::Fixed(delay) => write!(f, ", {:?} backoff", delay),
+            Backoff::Exponential { initial, max } => write!(f, ", exponential backoff {:?}..{:?}", initial, max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(RetryPolicy::parse("1"), Ok(RetryPolicy::NONE));
+        assert_eq!(RetryPolicy::parse("3:fixed:1ms").unwrap().backoff, Backoff::Fixed(Duration::from_millis(1)));
+        assert_eq!(
+            RetryPolicy::parse("5:exp:100us:10ms"),
+            Ok(RetryPolicy {
+                max_attempts: 5,
+                backoff: Backoff::Exponential { initial: Duration::from_micros(100), max: Duration::from_millis(10) },
+            })
+        );
+        for bad in ["0", "x", "3:fixed", "3:exp:1ms:1us", "3:linear:1ms"] {
+            assert!(RetryPolicy::parse(bad).is_err(), "{} should not parse", bad);
+        }
+        assert_eq!(RetryPolicy::parse("3:fixed:1ms").unwrap().to_string(), "up to 3 attempts, 1ms backoff");
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        let policy = RetryPolicy::parse("3").unwrap();
+        assert!(policy.should_retry(&TaskError::Timeout, 1));
+        assert!(policy.should_retry(&TaskError::Transient("dropped connection".into()), 2));
+        assert!(!policy.should_retry(&TaskError::Timeout, 3));
+        assert!(!policy.should_retry(&TaskError::DivisionByZero, 1));
+        assert!(!policy.should_retry(&TaskError::Cancelled, 1));
+        assert!(!RetryPolicy::NONE.should_retry(&TaskError::Timeout, 1));
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = RetryPolicy::parse("10:exp:1ms:5ms").unwrap();
+        let delays: Vec<u128> = (1..=5).map(|attempts| policy.backoff(attempts).as_millis()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+        assert_eq!(policy.backoff(100), Duration::from_millis(5));
+    }
+}
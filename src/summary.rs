@@ -0,0 +1,188 @@
+//! Human-readable summaries of benchmark runs.
+//!
+//! Every summary is returned as an `impl Display` rather than printed, so callers decide
+//! whether it goes to stdout, a log or nowhere. Each one ends with a newline.
+
+use crate::bench::Stats;
+use crate::executor::ExecutionResult;
+use std::fmt;
+use std::time::Duration;
+
+/// Whether two executions of the same batch produced identical outputs, apart from tasks
+/// interrupted by a deadline.
+///
+/// # Arguments
+/// * `expected` - The reference execution (normally the serial run).
+/// * `actual` - The execution being verified.
+/// * `label` - Name of the execution strategy being verified.
+pub fn verification<'a>(expected: &'a ExecutionResult, actual: &'a ExecutionResult, label: &'a str) -> impl fmt::Display + 'a {
+    fmt::from_fn(move |f| {
+        let mismatches = actual.mismatches(expected);
+        if mismatches == 0 {
+            writeln!(f, "Verified {} task outputs: serial and {} results match.", actual.outputs.len(), label)
+        } else {
+            writeln!(f, "⚠️  {} of {} task outputs differ between serial and {} runs.",
+                mismatches, expected.outputs.len(), label)
+        }
+    })
+}
+
+/// Timing statistics for serial execution and each concurrent executor, followed by each
+/// executor's speedup over serial execution.
+///
+/// Speedups compare mean durations; the 95% confidence intervals show whether a
+/// difference is larger than the run-to-run noise.
+///
+/// # Arguments
+/// * `serial` - Statistics of the serial task execution.
+/// * `concurrent` - Name and statistics of each concurrent task execution.
+pub fn timings<'a>(serial: &'a Stats, concurrent: &'a [(&'a str, &'a Stats)]) -> impl fmt::Display + 'a {
+    fmt::from_fn(move |f| {
+        writeln!(f, "\n=== Execution Time Summary ({} measured runs) ===", serial.samples.len())?;
+        writeln!(f, "{:<16}{:>12}{:>12}{:>12}{:>12}{:>12}   95% CI",
+            "Mode", "Mean", "Median", "Std dev", "Min", "Max")?;
+        for (name, stats) in std::iter::once(("Serial", serial)).chain(concurrent.iter().copied()) {
+            writeln!(f, "{:<16}{:>12}{:>12}{:>12}{:>12}{:>12}   [{:.2?}, {:.2?}]",
+                name,
+                format!("{:.2?}", stats.mean),
+                format!("{:.2?}", stats.median),
+                format!("{:.2?}", stats.std_dev),
+                format!("{:.2?}", stats.min),
+                format!("{:.2?}", stats.max),
+                stats.ci95.0,
+                stats.ci95.1)?;
+        }
+
+        // Compare each mean and report whether concurrency improved or hurt performance
+        for (name, stats) in concurrent {
+            if serial.mean > stats.mean {
+                let speedup = serial.mean.as_secs_f64() / stats.mean.as_secs_f64();
+                writeln!(f, "{} execution was {:.2}× faster.", name, speedup)?;
+            } else if stats.mean > serial.mean {
+                let slowdown = stats.mean.as_secs_f64() / serial.mean.as_secs_f64();
+                writeln!(f, "⚠️  Serial execution was {:.2}× faster than {}.", slowdown, name)?;
+            } else {
+                writeln!(f, "Serial and {} execution times were equal.", name)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// How many tasks of an execution were retried and failed, grouped by the kind of error,
+/// and the message of every task that panicked.
+///
+/// # Arguments
+/// * `label` - Name of the execution strategy being summarized.
+/// * `result` - The execution whose failures should be reported.
+pub fn failures<'a>(label: &'a str, result: &'a ExecutionResult) -> impl fmt::Display + 'a {
+    fmt::from_fn(move |f| {
+        let failures = result.failures_by_kind();
+        let (retries, recovered) = result.retries();
+        if retries > 0 {
+            writeln!(f, "{} execution: {} retries; {} tasks succeeded after retrying.", label, retries, recovered)?;
+        }
+        if failures.is_empty() {
+            return writeln!(f, "{} execution: all {} tasks succeeded.", label, result.outputs.len());
+        }
+
+        let failed: usize = failures.values().sum();
+        writeln!(f, "{} execution: {} of {} tasks failed.", label, failed, result.outputs.len())?;
+        for (kind, count) in failures {
+            writeln!(f, "  {:<18} {}", kind, count)?;
+        }
+        for (index, message) in result.panics() {
+            writeln!(f, "  task {} panicked: {}", index, message)?;
+        }
+        Ok(())
+    })
+}
+
+/// How busy each worker thread was, how evenly the batch was spread across them, and how
+/// much the workers contended for the shared queue's lock.
+///
+/// Empty for executors that do not report per-worker statistics.
+///
+/// # Arguments
+/// * `label` - Name of the execution mode, used as the table heading.
+/// * `result` - The execution whose workers are summarized.
+pub fn workers<'a>(label: &'a str, result: &'a ExecutionResult) -> impl fmt::Display + 'a {
+    fmt::from_fn(move |f| {
+        let Some(imbalance) = result.imbalance() else {
+            return Ok(());
+        };
+
+        writeln!(f, "\n--- Worker utilization: {} ---", label)?;
+        writeln!(
+            f,
+            "{:<8}{:>8}{:>8}{:>12}{:>12}{:>12}{:>11}{:>12}{:>13}",
+            "Worker", "Tasks", "Errors", "Busy", "Lock wait", "Lock hold", "Contended", "Idle", "Utilization"
+        )?;
+        for (id, worker) in result.workers.iter().enumerate() {
+            writeln!(
+                f,
+                "{:<8}{:>8}{:>8}{:>12}{:>12}{:>12}{:>10.1}%{:>12}{:>12.1}%",
+                id,
+                worker.tasks,
+                worker.errors,
+                format!("{:.2?}", worker.busy),
+                format!("{:.2?}", worker.lock.wait),
+                format!("{:.2?}", worker.lock.hold),
+                worker.lock.contention_rate() * 100.0,
+                format!("{:.2?}", worker.idle),
+                worker.utilization(result.duration) * 100.0
+            )?;
+        }
+        writeln!(f, "Load imbalance: {:.2} (busiest worker's busy time / mean; 1.00 is perfectly balanced)", imbalance)?;
+
+        let lock = result.lock_totals();
+        let busy: Duration = result.workers.iter().map(|worker| worker.busy).sum();
+        writeln!(
+            f,
+            "Lock contention: {} of {} acquisitions contended ({:.1}%), {:.2?} waiting, {:.2?} held",
+            lock.contended,
+            lock.acquisitions,
+            lock.contention_rate() * 100.0,
+            lock.wait,
+            lock.hold
+        )?;
+        if !busy.is_zero() {
+            writeln!(
+                f,
+                "Lock time / work time: {:.2} (wait + hold over time spent running tasks)",
+                (lock.wait + lock.hold).as_secs_f64() / busy.as_secs_f64()
+            )?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{execute_concurrently, execute_serially, TaskSettings};
+    use crate::task::TaskType;
+
+    #[test]
+    fn test_summaries() {
+        let tasks = vec![TaskType::Compute { a: 1, b: 2 }, TaskType::Divide { numerator: 1, denominator: 0 }];
+        let serial = execute_serially(&tasks, TaskSettings::DEFAULT);
+        let concurrent = execute_concurrently(&tasks, 2, TaskSettings::DEFAULT);
+
+        assert_eq!(
+            verification(&serial, &concurrent, "Mutex queue").to_string(),
+            "Verified 2 task outputs: serial and Mutex queue results match.\n"
+        );
+        assert_eq!(
+            failures("Serial", &serial).to_string(),
+            "Serial execution: 1 of 2 tasks failed.\n  division by zero   1\n"
+        );
+        assert_eq!(workers("Serial", &execute_serially(&[], TaskSettings::DEFAULT)).to_string(), "");
+        assert!(workers("Mutex queue", &concurrent).to_string().contains("Load imbalance"));
+
+        let fast = Stats::from_samples(vec![Duration::from_micros(10), Duration::from_micros(10)]);
+        let slow = Stats::from_samples(vec![Duration::from_micros(20), Duration::from_micros(20)]);
+        let text = timings(&slow, &[("Mutex queue", &fast)]).to_string();
+        assert!(text.ends_with("Mutex queue execution was 2.00× faster.\n"), "{}", text);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::bench::{measure_executor, BenchConfig, Stats};
+use crate::executor::{Executor, TaskSettings};
+use crate::task::TaskType;
+use std::fmt;
+
+/// Parallel efficiency below which scaling is considered to have flattened.
+const FLAT_EFFICIENCY: f64 = 0.5;
+
+/// The measurements taken at one thread count of a sweep.
+pub struct SweepPoint {
+    pub threads: u32,
+    pub stats: Stats,
+    /// Mean single-thread duration divided by the mean duration at `threads`.
+    pub speedup: f64,
+    /// `speedup / threads`; 1.0 means perfectly linear scaling.
+    pub efficiency: f64,
+}
+
+/// The scaling curve of one executor over a range of thread counts.
+pub struct SweepResult {
+    pub executor: Executor,
+    /// One point per measured thread count (always including 1), in ascending order.
+    pub points: Vec<SweepPoint>,
+    /// Serial fraction of the workload estimated by fitting Amdahl's law to the points.
+    pub serial_fraction: f64,
+}
+
+impl SweepResult {
+    /// The upper bound on speedup implied by the fitted serial fraction, if it is non-zero.
+    pub fn max_speedup(&self) -> Option<f64> {
+        (self.serial_fraction > 0.0).then(|| 1.0 / self.serial_fraction)
+    }
+
+    /// The first thread count whose parallel efficiency dropped below `FLAT_EFFICIENCY`.
+    pub fn flattens_at(&self) -> Option<u32> {
+        self.points
+            .iter()
+            .find(|point| point.efficiency < FLAT_EFFICIENCY)
+            .map(|point| point.threads)
+    }
+}
+
+/// Benchmarks `executor` at every thread count in `thread_counts`.
+///
+/// Speedup is measured against the same executor running on a single thread, so the
+/// curve shows how the executor scales rather than how it compares to the serial loop.
+/// The single-thread point is always measured and reported, even if `thread_counts` leaves it out.
+///
+/// # Arguments
+/// * `tasks` - The batch to run at every point.
+/// * `executor` - The concurrent executor to sweep.
+/// * `thread_counts` - Thread counts to measure; duplicates and zero are ignored.
+/// * `settings` - Simulated load and timeouts applied to each task.
+/// * `config` - Warmup and repetitions used at every point.
+/// * `on_point` - Called with each thread count just before it is measured, to report progress.
+pub fn sweep(
+    tasks: &[TaskType],
+    executor: Executor,
+    thread_counts: &[u32],
+    settings: TaskSettings,
+    config: &BenchConfig,
+    mut on_point: impl FnMut(u32),
+) -> SweepResult {
+    let mut counts: Vec<u32> = thread_counts.iter().copied().filter(|&n| n > 0).collect();
+    counts.push(1);
+    counts.sort_unstable();
+    counts.dedup();
+
+    let mut measured = Vec::with_capacity(counts.len());
+    for threads in counts {
+        on_point(threads);
+        let measurement = measure_executor(tasks, executor, threads, settings.clone(), config);
+        measured.push((threads, measurement.stats));
+    }
+
+    let baseline = measured[0].1.mean.as_secs_f64();
+    let points: Vec<SweepPoint> = measured
+        .into_iter()
+        .map(|(threads, stats)| {
+            let speedup = baseline / stats.mean.as_secs_f64();
+            SweepPoint { threads, stats, speedup, efficiency: speedup / threads as f64 }
+        })
+        .collect();
+
+    let curve: Vec<(u32, f64)> = points.iter().map(|point| (point.threads, point.speedup)).collect();
+    SweepResult { executor, points, serial_fraction: fit_amdahl(&curve) }
+}
+
+/// Estimates the serial fraction `f` of a workload from measured `(threads, speedup)` pairs.
+///
+/// Amdahl's law predicts `1 / S(p) = f + (1 - f) / p`, which rearranges to the line
+/// `1 / S(p) - 1 / p = f * (1 - 1 / p)` through the origin. `f` is its least-squares slope,
+/// clamped to `[0, 1]`. Single-thread points carry no information and are skipped.
+///
+/// # Returns
+/// The fitted serial fraction, or `0.0` if there are no multi-thread points.
+pub fn fit_amdahl(curve: &[(u32, f64)]) -> f64 {
+    let (mut xy, mut xx) = (0.0, 0.0);
+    for &(threads, speedup) in curve.iter().filter(|(threads, _)| *threads > 1) {
+        let inverse_p = 1.0 / threads as f64;
+        let x = 1.0 - inverse_p;
+        let y = 1.0 / speedup - inverse_p;
+        xy += x * y;
+        xx += x * x;
+    }
+    if xx == 0.0 {
+        0.0
+    } else {
+        (xy / xx).clamp(0.0, 1.0)
+    }
+}
+
+/// The scaling table and Amdahl estimate.
+impl fmt::Display for SweepResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n=== Thread Scaling: {} ===", self.executor.name())?;
+        writeln!(f, "{:>8}{:>12}{:>12}{:>10}{:>12}", "Threads", "Mean", "Std dev", "Speedup", "Efficiency")?;
+        for point in &self.points {
+            writeln!(f, "{:>8}{:>12}{:>12}{:>9.2}×{:>11.1}%",
+                point.threads,
+                format!("{:.2?}", point.stats.mean),
+                format!("{:.2?}", point.stats.std_dev),
+                point.speedup,
+                point.efficiency * 100.0)?;
+        }
+
+        writeln!(f, "Amdahl serial fraction: {:.1}%", self.serial_fraction * 100.0)?;
+        match self.max_speedup() {
+            Some(limit) => writeln!(f, "Maximum achievable speedup: {:.2}×", limit)?,
+            None => writeln!(f, "Maximum achievable speedup: unbounded (no serial fraction detected)")?,
+        }
+        match self.flattens_at() {
+            Some(threads) => writeln!(f, "Scaling flattens at {} threads (efficiency below {:.0}%).",
+                threads, FLAT_EFFICIENCY * 100.0),
+            None => writeln!(f, "Efficiency stayed above {:.0}% at every thread count.", FLAT_EFFICIENCY * 100.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Speedup Amdahl's law predicts for serial fraction `f` on `p` threads.
+    fn amdahl(f: f64, p: u32) -> f64 {
+        1.0 / (f + (1.0 - f) / p as f64)
+    }
+
+    #[test]
+    fn test_fit_amdahl_recovers_serial_fraction() {
+        let curve: Vec<_> = (1..=16).map(|p| (p, amdahl(0.1, p))).collect();
+        assert!((fit_amdahl(&curve) - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fit_amdahl_edge_cases() {
+        // Perfectly linear scaling has no serial part
+        assert_eq!(fit_amdahl(&[(1, 1.0), (2, 2.0), (4, 4.0)]), 0.0);
+        // Getting slower with more threads is clamped to fully serial
+        assert_eq!(fit_amdahl(&[(2, 0.5), (4, 0.25)]), 1.0);
+        // Nothing to fit without multi-thread points
+        assert_eq!(fit_amdahl(&[(1, 1.0)]), 0.0);
+    }
+}
@@ -1,4 +1,8 @@
+use crate::cancel::CancellationToken;
 use crate::helpers;
+use crate::load::LoadModel;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// A trait representing a unit of work that can be executed.
 ///
@@ -9,18 +13,117 @@ pub trait Task {
     /// Executes the task.
     ///
     /// # Arguments
-    /// * `simulate_load` - If true, introduces a brief delay to mimic heavier workloads.
+    /// * `load` - Simulated latency to spend before computing, to mimic heavier workloads.
+    /// * `cancel` - Checked periodically by long-running work; once it fires the task stops
+    ///   with `TaskError::Timeout` or `TaskError::Cancelled`.
     ///
     /// # Returns
-    /// * `Ok(())` on successful task execution.
-    /// * `Err(String)` if an error occurred.
-    fn run(&self, simulate_load: bool) -> Result<(), String>;
+    /// * `Ok(TaskOutput)` holding the computed value on successful task execution.
+    /// * `Err(TaskError)` describing why the task could not produce a value.
+    fn run(&self, load: &LoadModel, cancel: &CancellationToken) -> Result<TaskOutput, TaskError>;
+}
+
+/// Every way a task can fail to produce an output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaskError {
+    /// A `Divide` task was given a zero denominator.
+    DivisionByZero,
+    /// A `ModuloExponentiation` task was given a zero modulus.
+    ZeroModulus,
+    /// The result does not fit in the output type.
+    Overflow,
+    /// The task did not finish before its deadline.
+    Timeout,
+    /// The task was cancelled before it could finish.
+    Cancelled,
+    /// The task panicked; holds the panic payload message.
+    Panicked(String),
+    /// The task's parameters are outside the range it supports.
+    InvalidInput(String),
+    /// A failure that may not recur if the task is run again, such as a dropped connection.
+    Transient(String),
+    /// A task this one depends on failed, so it was not run; names the failed dependency.
+    UpstreamFailed(String),
+}
+
+impl TaskError {
+    /// Whether the task was stopped by a deadline or cancellation rather than failing on
+    /// its own, so its outcome can differ between otherwise identical runs.
+    pub fn is_interrupted(&self) -> bool {
+        matches!(self, TaskError::Timeout | TaskError::Cancelled)
+    }
+
+    /// Whether running the task again might succeed, so a `RetryPolicy` may retry it.
+    ///
+    /// Cancellation is final, since it means the whole batch is being stopped.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, TaskError::Timeout | TaskError::Transient(_))
+    }
+
+    /// A short, stable label for the kind of error, used to group failures in summaries.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TaskError::DivisionByZero => "division by zero",
+            TaskError::ZeroModulus => "zero modulus",
+            TaskError::Overflow => "overflow",
+            TaskError::Timeout => "timeout",
+            TaskError::Cancelled => "cancelled",
+            TaskError::Panicked(_) => "panicked",
+            TaskError::InvalidInput(_) => "invalid input",
+            TaskError::Transient(_) => "transient",
+            TaskError::UpstreamFailed(_) => "upstream failed",
+        }
+    }
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::DivisionByZero => write!(f, "Division by zero."),
+            TaskError::ZeroModulus => write!(f, "Modulus cannot be zero."),
+            TaskError::Overflow => write!(f, "Arithmetic overflow."),
+            TaskError::Timeout => write!(f, "Task timed out."),
+            TaskError::Cancelled => write!(f, "Task was cancelled."),
+            TaskError::Panicked(message) => write!(f, "Task panicked: {}", message),
+            TaskError::InvalidInput(reason) => write!(f, "Invalid input: {}", reason),
+            TaskError::Transient(reason) => write!(f, "Transient failure: {}", reason),
+            TaskError::UpstreamFailed(reason) => write!(f, "Dependency failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+/// The value produced by a successfully executed task.
+///
+/// Each `TaskType` variant maps onto exactly one output variant, so callers can
+/// inspect and verify results instead of only timing them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaskOutput {
+    /// Signed result of `Compute`, `Divide` and `Multiply`.
+    Integer(i64),
+    /// Unsigned result of `Fibonacci`, `Factorial` and `ModuloExponentiation`.
+    BigInteger(u64),
+    /// Result of `PrimeCheck`.
+    Bool(bool),
+}
+
+impl fmt::Display for TaskOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskOutput::Integer(value) => write!(f, "{}", value),
+            TaskOutput::BigInteger(value) => write!(f, "{}", value),
+            TaskOutput::Bool(value) => write!(f, "{}", value),
+        }
+    }
 }
 
 /// Enum representing all possible types of tasks supported by the system.
 ///
 /// Each variant contains the data necessary to perform that specific task.
-#[derive(Clone)]
+/// Serialized as an object tagged with the variant's `key`, e.g. `{"type": "fibonacci", "n": 20}`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
 pub enum TaskType {
     Compute { a: i32, b: i32 },
     Fibonacci { n: u32 },
@@ -31,49 +134,149 @@ pub enum TaskType {
     ModuloExponentiation { base: u64, exponent: u64, modulus: u64 },
 }
 
+impl TaskType {
+    /// The `key` of every variant, in declaration order.
+    pub const ALL_KEYS: [&'static str; 7] =
+        ["compute", "fibonacci", "divide", "multiply", "factorial", "prime_check", "modulo_exponentiation"];
+
+    /// The position of the variant in declaration order, which is also its slot in `ALL_KEYS`.
+    pub fn index(&self) -> usize {
+        match self {
+            TaskType::Compute { .. } => 0,
+            TaskType::Fibonacci { .. } => 1,
+            TaskType::Divide { .. } => 2,
+            TaskType::Multiply { .. } => 3,
+            TaskType::Factorial { .. } => 4,
+            TaskType::PrimeCheck { .. } => 5,
+            TaskType::ModuloExponentiation { .. } => 6,
+        }
+    }
+
+    /// The snake_case name of the variant, as used in task files and workload specs.
+    pub fn key(&self) -> &'static str {
+        TaskType::ALL_KEYS[self.index()]
+    }
+}
+
 /// Implements the Task trait for TaskType.
 ///
 /// Handles dispatching logic to the appropriate helper function depending on the task variant.
 impl Task for TaskType {
-    fn run(&self, simulate_load: bool) -> Result<(), String> {
-        if simulate_load {
-        }
-
+    fn run(&self, load: &LoadModel, cancel: &CancellationToken) -> Result<TaskOutput, TaskError> {
+        // Spend the simulated latency first, so every executor models it identically
+        load.apply(cancel)?;
         match self {
             TaskType::Compute { a, b } => {
-                let _ = helpers::compute::<i32>(*a, *b);
-                Ok(())
+                // Widen before adding so the result can never overflow
+                let sum = helpers::compute::<i64>(*a as i64, *b as i64);
+                Ok(TaskOutput::Integer(sum))
             }
             TaskType::Fibonacci { n } => {
-                let _ = helpers::fibonacci(*n as u64);
-                Ok(())
+                if *n as u64 > helpers::FIBONACCI_MAX_N {
+                    return Err(TaskError::Overflow);
+                }
+                Ok(TaskOutput::BigInteger(helpers::fibonacci(*n as u64)))
             }
             TaskType::Divide { numerator, denominator } => {
                 if *denominator == 0 {
-                    return Err("Division by zero.".into());
+                    return Err(TaskError::DivisionByZero);
                 }
-                let _ = helpers::divide::<i32>(*numerator, *denominator);
-                Ok(())
+                let quotient = helpers::divide::<i64>(*numerator as i64, *denominator as i64);
+                Ok(TaskOutput::Integer(quotient))
             }
             TaskType::Multiply { a, b } => {
-                let _ = helpers::multiply::<i32>(*a, *b);
-                Ok(())
+                let product = helpers::multiply::<i64>(*a as i64, *b as i64);
+                Ok(TaskOutput::Integer(product))
             }
             TaskType::Factorial { n } => {
-                let _ = helpers::factorial(*n as u64);
-                Ok(())
+                if *n as u64 > helpers::FACTORIAL_MAX_N {
+                    return Err(TaskError::Overflow);
+                }
+                Ok(TaskOutput::BigInteger(helpers::factorial(*n as u64)))
             }
             TaskType::PrimeCheck { n } => {
-                let _ = helpers::prime_check(*n);
-                Ok(())
+                Ok(TaskOutput::Bool(helpers::prime_check(*n, cancel)?))
             }
             TaskType::ModuloExponentiation { base, exponent, modulus } => {
                 if *modulus == 0 {
-                    return Err("Modulus cannot be zero.".into());
+                    return Err(TaskError::ZeroModulus);
+                }
+                if *modulus > helpers::MOD_EXP_MAX_MODULUS {
+                    return Err(TaskError::InvalidInput(format!(
+                        "modulus {} exceeds {}", modulus, helpers::MOD_EXP_MAX_MODULUS
+                    )));
                 }
-                let _ = helpers::mod_exp(*base, *exponent, *modulus);
-                Ok(())
+                Ok(TaskOutput::BigInteger(helpers::mod_exp(*base, *exponent, *modulus)))
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_outputs() {
+        assert_eq!(TaskType::Compute { a: 2, b: 3 }.run(&LoadModel::NONE, &CancellationToken::new()), Ok(TaskOutput::Integer(5)));
+        assert_eq!(TaskType::Divide { numerator: 9, denominator: 2 }.run(&LoadModel::NONE, &CancellationToken::new()), Ok(TaskOutput::Integer(4)));
+        assert_eq!(TaskType::Multiply { a: -4, b: 6 }.run(&LoadModel::NONE, &CancellationToken::new()), Ok(TaskOutput::Integer(-24)));
+        assert_eq!(TaskType::Fibonacci { n: 7 }.run(&LoadModel::NONE, &CancellationToken::new()), Ok(TaskOutput::BigInteger(21)));
+        assert_eq!(TaskType::Factorial { n: 5 }.run(&LoadModel::NONE, &CancellationToken::new()), Ok(TaskOutput::BigInteger(120)));
+        assert_eq!(TaskType::PrimeCheck { n: 97 }.run(&LoadModel::NONE, &CancellationToken::new()), Ok(TaskOutput::Bool(true)));
+        assert_eq!(
+            TaskType::ModuloExponentiation { base: 2, exponent: 3, modulus: 5 }.run(&LoadModel::NONE, &CancellationToken::new()),
+            Ok(TaskOutput::BigInteger(3))
+        );
+    }
+
+    #[test]
+    fn test_keys_follow_declaration_order() {
+        let tasks = [
+            TaskType::Compute { a: 1, b: 2 },
+            TaskType::Fibonacci { n: 3 },
+            TaskType::Divide { numerator: 4, denominator: 5 },
+            TaskType::Multiply { a: 6, b: 7 },
+            TaskType::Factorial { n: 8 },
+            TaskType::PrimeCheck { n: 9 },
+            TaskType::ModuloExponentiation { base: 2, exponent: 3, modulus: 5 },
+        ];
+        assert_eq!(tasks.len(), TaskType::ALL_KEYS.len());
+        for (index, task) in tasks.iter().enumerate() {
+            assert_eq!(task.index(), index);
+            // The serde tag is derived from the variant name, so it catches a misspelt key
+            assert_eq!(serde_json::to_value(task).unwrap()["type"], task.key());
+        }
+    }
+
+    #[test]
+    fn test_run_errors() {
+        assert_eq!(TaskType::Divide { numerator: 1, denominator: 0 }.run(&LoadModel::NONE, &CancellationToken::new()), Err(TaskError::DivisionByZero));
+        assert_eq!(
+            TaskType::ModuloExponentiation { base: 2, exponent: 3, modulus: 0 }.run(&LoadModel::NONE, &CancellationToken::new()),
+            Err(TaskError::ZeroModulus)
+        );
+        assert_eq!(TaskType::Factorial { n: 21 }.run(&LoadModel::NONE, &CancellationToken::new()), Err(TaskError::Overflow));
+        assert_eq!(TaskType::Fibonacci { n: 93 }.run(&LoadModel::NONE, &CancellationToken::new()), Err(TaskError::Overflow));
+        assert_eq!(
+            TaskType::ModuloExponentiation { base: 2, exponent: 3, modulus: u64::MAX }.run(&LoadModel::NONE, &CancellationToken::new())
+                .map_err(|e| e.kind()),
+            Err("invalid input")
+        );
+    }
+
+    #[test]
+    fn test_run_times_out() {
+        let expired = CancellationToken::with_timeout(Some(std::time::Duration::ZERO));
+        let task = TaskType::PrimeCheck { n: 4294967291 };
+        assert_eq!(task.run(&LoadModel::NONE, &expired), Err(TaskError::Timeout));
+        // Quick tasks never reach a check and still succeed
+        assert_eq!(TaskType::Compute { a: 1, b: 2 }.run(&LoadModel::NONE, &expired), Ok(TaskOutput::Integer(3)));
+    }
+
+    #[test]
+    fn test_multiply_does_not_overflow() {
+        let task = TaskType::Multiply { a: i32::MAX, b: i32::MAX };
+        assert_eq!(task.run(&LoadModel::NONE, &CancellationToken::new()), Ok(TaskOutput::Integer(i32::MAX as i64 * i32::MAX as i64)));
+    }
+}
@@ -0,0 +1,273 @@
+//! Reading and writing batches of tasks as JSON or CSV files.
+//!
+//! JSON files hold an array of task objects tagged by variant:
+//! `[{"type": "compute", "a": 1, "b": 2}, {"type": "fibonacci", "n": 20}]`.
+//!
+//! CSV files hold one task per line: the variant followed by its fields in declaration
+//! order, e.g. `compute,1,2` or `modulo_exponentiation,2,10,7`. An optional header line
+//! starting with `type`, blank lines and lines starting with `#` are ignored.
+//!
+//! Any task can set its own priority for the priority executor, overriding its kind's: with
+//! a `"priority": "high"` field in JSON, or an extra last field in CSV, e.g. `compute,1,2,high`.
+
+use crate::priority::{Priorities, Priority};
+use crate::task::TaskType;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Header line written at the top of CSV task files.
+const CSV_HEADER: &str = "type,arg1,arg2,arg3";
+
+/// The on-disk formats a task batch can be stored in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskFileFormat {
+    Json,
+    Csv,
+}
+
+impl TaskFileFormat {
+    /// Picks the format from a file's extension; report files use the same rule.
+    pub fn from_path(path: &Path) -> Result<TaskFileFormat, String> {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("json") => Ok(TaskFileFormat::Json),
+            Some("csv") => Ok(TaskFileFormat::Csv),
+            _ => Err(format!("Cannot tell the format of '{}'; use a .json or .csv extension.", path.display())),
+        }
+    }
+}
+
+/// A batch of tasks as read from a task file, and the priority each task set for itself,
+/// if any, in the same order.
+pub type TaskList = (Vec<TaskType>, Vec<Option<Priority>>);
+
+/// A task object in a JSON task file.
+#[derive(Serialize, Deserialize)]
+struct TaskEntry {
+    #[serde(flatten)]
+    task: TaskType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+}
+
+/// Reads a batch of tasks from a `.json` or `.csv` file.
+pub fn load_tasks(path: &Path) -> Result<TaskList, String> {
+    let format = TaskFileFormat::from_path(path)?;
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let tasks = match format {
+        TaskFileFormat::Json => parse_json(&text),
+        TaskFileFormat::Csv => parse_csv(&text),
+    };
+    tasks.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Writes a batch of tasks to a `.json` or `.csv` file, replacing any existing file.
+///
+/// # Arguments
+/// * `path` - The file to write.
+/// * `tasks` - The batch.
+/// * `priorities` - The batch's priorities; only those set by individual tasks are written.
+pub fn save_tasks(path: &Path, tasks: &[TaskType], priorities: &Priorities) -> Result<(), String> {
+    let text = match TaskFileFormat::from_path(path)? {
+        TaskFileFormat::Json => to_json(tasks, priorities),
+        TaskFileFormat::Csv => to_csv(tasks, priorities),
+    };
+    fs::write(path, text).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/// Parses a JSON array of task objects.
+pub fn parse_json(text: &str) -> Result<TaskList, String> {
+    let entries: Vec<TaskEntry> = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    Ok(entries.into_iter().map(|entry| (entry.task, entry.priority)).unzip())
+}
+
+/// Serializes tasks as a JSON array with one task object per line, including the priority
+/// of each task that set its own.
+pub fn to_json(tasks: &[TaskType], priorities: &Priorities) -> String {
+    let lines: Vec<String> = tasks
+        .iter()
+        .enumerate()
+        .map(|(index, task)| {
+            let entry = TaskEntry { task: task.clone(), priority: priorities.of_task(index) };
+            format!("  {}", serde_json::to_string(&entry).expect("Tasks are always serializable"))
+        })
+        .collect();
+    if lines.is_empty() {
+        "[]\n".into()
+    } else {
+        format!("[\n{}\n]\n", lines.join(",\n"))
+    }
+}
+
+/// Parses CSV task lines, reporting the 1-based line number of the first bad line.
+pub fn parse_csv(text: &str) -> Result<TaskList, String> {
+    let mut tasks = (Vec::new(), Vec::new());
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (number == 0 && line.starts_with("type")) {
+            continue;
+        }
+        let (task, priority) = parse_csv_line(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        tasks.0.push(task);
+        tasks.1.push(priority);
+    }
+    Ok(tasks)
+}
+
+/// Serializes tasks as CSV with a header line, adding the priority of each task that set
+/// its own as an extra last field.
+pub fn to_csv(tasks: &[TaskType], priorities: &Priorities) -> String {
+    let mut text = String::from(CSV_HEADER);
+    text.push('\n');
+    for (index, task) in tasks.iter().enumerate() {
+        let fields = match task {
+            TaskType::Compute { a, b } | TaskType::Multiply { a, b } => format!("{},{}", a, b),
+            TaskType::Divide { numerator, denominator } => format!("{},{}", numerator, denominator),
+            TaskType::Fibonacci { n } | TaskType::Factorial { n } | TaskType::PrimeCheck { n } => n.to_string(),
+            TaskType::ModuloExponentiation { base, exponent, modulus } => {
+                format!("{},{},{}", base, exponent, modulus)
+            }
+        };
+        match priorities.of_task(index) {
+            Some(priority) => text.push_str(&format!("{},{},{}\n", task.key(), fields, priority.label())),
+            None => text.push_str(&format!("{},{}\n", task.key(), fields)),
+        }
+    }
+    text
+}
+
+/// Parses one CSV task line such as `divide,10,3` or, with a priority, `divide,10,3,high`.
+fn parse_csv_line(line: &str) -> Result<(TaskType, Option<Priority>), String> {
+    let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+    let (key, mut values) = cells.split_first().expect("split always yields at least one cell");
+
+    // The field names double as the expected arity and as labels for error messages
+    let fields: &[&str] = match *key {
+        "compute" | "multiply" => &["a", "b"],
+        "divide" => &["numerator", "denominator"],
+        "fibonacci" | "factorial" | "prime_check" => &["n"],
+        "modulo_exponentiation" => &["base", "exponent", "modulus"],
+        other => return Err(format!("unknown task variant '{}'", other)),
+    };
+    // One field more than the task has is the priority it sets for itself
+    let mut priority = None;
+    if values.len() == fields.len() + 1 {
+        let (last, rest) = values.split_last().expect("at least one field");
+        priority = Some(Priority::parse(last)?);
+        values = rest;
+    }
+    if values.len() != fields.len() {
+        return Err(format!(
+            "'{}' expects {} field(s) ({}), got {}",
+            key, fields.len(), fields.join(", "), values.len()
+        ));
+    }
+
+    let field = |i: usize| Field { name: fields[i], raw: values[i] };
+    let task = match *key {
+        "compute" => TaskType::Compute { a: field(0).parse()?, b: field(1).parse()? },
+        "multiply" => TaskType::Multiply { a: field(0).parse()?, b: field(1).parse()? },
+        "divide" => TaskType::Divide { numerator: field(0).parse()?, denominator: field(1).parse()? },
+        "fibonacci" => TaskType::Fibonacci { n: field(0).parse()? },
+        "factorial" => TaskType::Factorial { n: field(0).parse()? },
+        "prime_check" => TaskType::PrimeCheck { n: field(0).parse()? },
+        _ => TaskType::ModuloExponentiation {
+            base: field(0).parse()?,
+            exponent: field(1).parse()?,
+            modulus: field(2).parse()?,
+        },
+    };
+    Ok((task, priority))
+}
+
+/// A named CSV cell, so parse errors can say which field was wrong.
+struct Field<'a> {
+    name: &'a str,
+    raw: &'a str,
+}
+
+impl Field<'_> {
+    fn parse<T: FromStr>(&self) -> Result<T, String> {
+        self.raw
+            .parse()
+            .map_err(|_| format!("invalid value '{}' for field '{}'", self.raw, self.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tasks() -> Vec<TaskType> {
+        vec![
+            TaskType::Compute { a: -1, b: 2 },
+            TaskType::Fibonacci { n: 20 },
+            TaskType::Divide { numerator: 10, denominator: 3 },
+            TaskType::Multiply { a: 4, b: 5 },
+            TaskType::Factorial { n: 6 },
+            TaskType::PrimeCheck { n: 97 },
+            TaskType::ModuloExponentiation { base: 2, exponent: 10, modulus: 7 },
+        ]
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let tasks = sample_tasks();
+        let unset = vec![None; tasks.len()];
+        assert_eq!(parse_json(&to_json(&tasks, &Priorities::EQUAL)).unwrap(), (tasks.clone(), unset.clone()));
+        assert_eq!(parse_csv(&to_csv(&tasks, &Priorities::EQUAL)).unwrap(), (tasks.clone(), unset));
+        assert_eq!(parse_json(&to_json(&[], &Priorities::EQUAL)).unwrap(), (vec![], vec![]));
+
+        let mut own = vec![None; tasks.len()];
+        own[1] = Some(Priority::High);
+        own[6] = Some(Priority::Low);
+        let priorities = Priorities::parse("compute:low").unwrap().with_task_priorities(own.clone());
+        assert_eq!(parse_json(&to_json(&tasks, &priorities)).unwrap(), (tasks.clone(), own.clone()));
+        assert_eq!(parse_csv(&to_csv(&tasks, &priorities)).unwrap(), (tasks, own));
+    }
+
+    #[test]
+    fn test_tasks_set_their_own_priority() {
+        let (tasks, priorities) = parse_json(r#"[{"type": "fibonacci", "n": 3, "priority": "high"}, {"type": "fibonacci", "n": 4}]"#).unwrap();
+        assert_eq!(tasks, vec![TaskType::Fibonacci { n: 3 }, TaskType::Fibonacci { n: 4 }]);
+        assert_eq!(priorities, vec![Some(Priority::High), None]);
+        assert!(parse_json(r#"[{"type": "fibonacci", "n": 3, "priority": "urgent"}]"#).is_err());
+
+        assert_eq!(parse_csv("divide,10,3,low\ndivide,10,3").unwrap().1, vec![Some(Priority::Low), None]);
+        assert_eq!(parse_csv("compute,1,2,3").unwrap_err(), "line 1: Unknown priority '3'; expected low, normal or high.");
+    }
+
+    #[test]
+    fn test_parse_csv_skips_comments_and_blanks() {
+        let text = "# fixed workload\n\nprime_check, 7\n  fibonacci,3\n";
+        assert_eq!(
+            parse_csv(text).unwrap().0,
+            vec![TaskType::PrimeCheck { n: 7 }, TaskType::Fibonacci { n: 3 }]
+        );
+    }
+
+    #[test]
+    fn test_csv_errors() {
+        assert_eq!(parse_csv("sort,1").unwrap_err(), "line 1: unknown task variant 'sort'");
+        assert_eq!(
+            parse_csv("compute,1,2\ndivide,1").unwrap_err(),
+            "line 2: 'divide' expects 2 field(s) (numerator, denominator), got 1"
+        );
+        assert_eq!(parse_csv("factorial,-3").unwrap_err(), "line 1: invalid value '-3' for field 'n'");
+    }
+
+    #[test]
+    fn test_json_errors() {
+        assert!(parse_json(r#"[{"type": "sort", "n": 1}]"#).unwrap_err().contains("unknown variant"));
+        assert!(parse_json(r#"[{"type": "fibonacci"}]"#).unwrap_err().contains("missing field `n`"));
+        assert!(parse_json(r#"[{"type": "fibonacci", "n": 1, "m": 2}]"#).unwrap_err().contains("unknown field"));
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert_eq!(TaskFileFormat::from_path(Path::new("a.JSON")), Ok(TaskFileFormat::Json));
+        assert_eq!(TaskFileFormat::from_path(Path::new("dir/b.csv")), Ok(TaskFileFormat::Csv));
+        assert!(TaskFileFormat::from_path(Path::new("c.txt")).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Exporting task executions as Chrome Trace Event JSON.
+//!
+//! The file can be opened in `chrome://tracing` or <https://ui.perfetto.dev>. Each execution
+//! mode becomes a process, each of its workers a thread track, and each task a duration
+//! event on the track of the worker that ran it.
+
+use crate::executor::ExecutionResult;
+use crate::task::TaskType;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Builds the trace for one or more labelled executions of the same batch.
+///
+/// Every attempt at a task becomes one span, starting at its offset from the start of the
+/// batch, on the track of the worker that made it. Tasks queued late, such as those
+/// `execute_dag` only releases once their dependencies finish, are therefore drawn where
+/// they actually ran, and a retried task's attempts are drawn separately with whatever ran
+/// in between. Attempts of a retried task are in the `retried` category, and their `attempt`
+/// argument numbers them from 1. Attempts without a recorded worker are left out.
+///
+/// # Arguments
+/// * `tasks` - The batch that was executed.
+/// * `runs` - Each execution mode's label and the execution to draw for it.
+pub fn to_trace(tasks: &[TaskType], runs: &[(&str, &ExecutionResult)]) -> Value {
+    let mut events = Vec::new();
+    for (pid, (label, result)) in runs.iter().enumerate() {
+        events.push(json!({
+            "name": "process_name", "ph": "M", "pid": pid, "tid": 0,
+            "args": { "name": label },
+        }));
+        let mut workers: Vec<usize> =
+            result.timings.iter().flat_map(|timing| &timing.runs).filter_map(|attempt| attempt.worker).collect();
+        workers.sort_unstable();
+        workers.dedup();
+        for worker in workers {
+            events.push(json!({
+                "name": "thread_name", "ph": "M", "pid": pid, "tid": worker,
+                "args": { "name": format!("worker {}", worker) },
+            }));
+        }
+
+        let records = tasks.iter().zip(&result.outputs).zip(&result.timings).enumerate();
+        for (index, ((task, output), timing)) in records {
+            let category = match timing.attempts {
+                0 | 1 => task.key().to_string(),
+                _ => format!("{},retried", task.key()),
+            };
+            for (number, attempt) in timing.runs.iter().enumerate() {
+                let Some(worker) = attempt.worker else {
+                    continue;
+                };
+                // Only the last attempt's outcome is kept; every earlier one failed and was retried
+                let outcome = match output {
+                    _ if number + 1 < timing.runs.len() => "retried".to_string(),
+                    Ok(value) => value.to_string(),
+                    Err(e) => e.to_string(),
+                };
+                events.push(json!({
+                    "name": task_label(task),
+                    "cat": category,
+                    "ph": "X",
+                    "pid": pid,
+                    "tid": worker,
+                    "ts": micros(attempt.start),
+                    "dur": micros(attempt.run),
+                    "args": {
+                        "index": index,
+                        "wait_us": micros(timing.wait),
+                        "attempt": number + 1,
+                        "result": outcome,
+                    },
+                }));
+            }
+        }
+    }
+    json!({ "traceEvents": events, "displayTimeUnit": "ns" })
+}
+
+/// Writes the trace built by `to_trace` to `path`, replacing any existing file.
+pub fn save_trace(path: &Path, tasks: &[TaskType], runs: &[(&str, &ExecutionResult)]) -> Result<(), String> {
+    let text = serde_json::to_string(&to_trace(tasks, runs)).expect("Traces are always serializable");
+    fs::write(path, text).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/// The task's variant and parameters, e.g. `fibonacci(n=20)`.
+fn task_label(task: &TaskType) -> String {
+    let fields = match serde_json::to_value(task) {
+        Ok(Value::Object(fields)) => fields,
+        _ => return task.key().to_string(),
+    };
+    let params: Vec<String> = fields
+        .iter()
+        .filter(|(name, _)| name.as_str() != "type")
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    format!("{}({})", task.key(), params.join(", "))
+}
+
+/// Trace timestamps and durations are in (fractional) microseconds.
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{execute_dag, TaskGraph};
+    use crate::executor::{execute_concurrently, execute_serially, TaskSettings};
+    use crate::load::LoadModel;
+    use crate::retry::RetryPolicy;
+    
+    #[test]
+    fn test_task_label() {
+        assert_eq!(task_label(&TaskType::Fibonacci { n: 20 }), "fibonacci(n=20)");
+        assert_eq!(
+            task_label(&TaskType::Divide { numerator: 7, denominator: -2 }),
+            "divide(denominator=-2, numerator=7)"
+        );
+    }
+
+    #[test]
+    fn test_trace_has_one_event_per_task() {
+        let tasks: Vec<TaskType> = (0..50).map(|n| TaskType::PrimeCheck { n }).collect();
+        let serial = execute_serially(&tasks, TaskSettings::DEFAULT);
+        let concurrent = execute_concurrently(&tasks, 3, TaskSettings::DEFAULT);
+        let trace = to_trace(&tasks, &[("Serial", &serial), ("Mutex queue", &concurrent)]);
+
+        let events = trace["traceEvents"].as_array().unwrap();
+        let spans: Vec<&Value> = events.iter().filter(|event| event["ph"] == "X").collect();
+        assert_eq!(spans.len(), 100);
+        assert!(spans.iter().all(|span| span["cat"] == "prime_check"));
+        assert!(spans.iter().filter(|span| span["pid"] == 0).all(|span| span["tid"] == 0));
+        assert!(spans.iter().filter(|span| span["pid"] == 1).all(|span| span["tid"].as_u64().unwrap() < 3));
+    }
+
+    #[test]
+    fn test_dag_spans_follow_dependencies() {
+        let graph = TaskGraph::parse_json(
+            r#"{ "tasks": [
+                { "id": "slow", "task": { "type": "fibonacci", "n": 10 } },
+                { "id": "other", "task": { "type": "prime_check", "n": 7 } },
+                { "id": "next", "task": { "type": "compute", "a": 0, "b": 1 }, "inputs": { "a": "slow" } },
+                { "id": "last", "task": { "type": "multiply", "a": 0, "b": 2 }, "inputs": { "a": "next" } }
+            ] }"#,
+        )
+        .unwrap();
+        let settings = TaskSettings { load: LoadModel::parse("fixed:2ms").unwrap(), ..TaskSettings::DEFAULT };
+        let result = execute_dag(&graph, 2, settings);
+        let trace = to_trace(&graph.bound_tasks(&result.outputs), &[("DAG executor", &result)]);
+
+        let events = trace["traceEvents"].as_array().unwrap();
+        let span = |index: usize| events.iter().find(|event| event["ph"] == "X" && event["args"]["index"] == index).unwrap();
+        let end = |span: &Value| span["ts"].as_f64().unwrap() + span["dur"].as_f64().unwrap();
+        for (index, node) in graph.nodes().iter().enumerate() {
+            for (_, from) in &node.inputs {
+                let (start, dependency) = (span(index)["ts"].as_f64().unwrap(), end(span(*from)));
+                assert!(start >= dependency, "'{}' started before '{}' ended", node.id, graph.nodes()[*from].id);
+            }
+        }
+        assert_eq!(span(2)["name"], "compute(a=89, b=1)");
+    }
+
+    #[test]
+    fn test_one_span_per_attempt() {
+        let tasks: Vec<TaskType> = (0..50).map(|n| TaskType::PrimeCheck { n }).collect();
+        let flaky = TaskSettings {
+            load: LoadModel::parse("fixed:20us").unwrap().with_failure_rate(0.5),
+            retry: RetryPolicy::parse("30").unwrap(),
+            ..TaskSettings::DEFAULT
+        };
+        let serial = execute_serially(&tasks, flaky.clone());
+        let concurrent = execute_concurrently(&tasks, 3, flaky);
+        let trace = to_trace(&tasks, &[("Serial", &serial), ("Mutex queue", &concurrent)]);
+
+        let events = trace["traceEvents"].as_array().unwrap();
+        let mut spans: Vec<&Value> = events.iter().filter(|event| event["ph"] == "X").collect();
+        let attempts: u32 = serial.timings.iter().chain(&concurrent.timings).map(|timing| timing.attempts).sum();
+        assert_eq!(spans.len(), attempts as usize);
+        assert!(spans.iter().any(|span| span["cat"] == "prime_check,retried" && span["args"]["attempt"] == 2));
+
+        // Attempts on the same worker never overlap, whatever ran between a task's retries
+        let track = |span: &Value| (span["pid"].as_u64().unwrap(), span["tid"].as_u64().unwrap());
+        let ts = |span: &Value| span["ts"].as_f64().unwrap();
+        spans.sort_by(|a, b| track(a).cmp(&track(b)).then(ts(a).total_cmp(&ts(b))));
+        for pair in spans.windows(2).filter(|pair| track(pair[0]) == track(pair[1])) {
+            let end = ts(pair[0]) + pair[0]["dur"].as_f64().unwrap();
+            assert!(ts(pair[1]) >= end, "{} overlaps {}", pair[1], pair[0]);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::task::TaskType;
+use rand::distributions::uniform::SampleUniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// An inclusive `[min, max]` range a task parameter is drawn from.
+///
+/// Serialized as a two-element array, e.g. `"n": [1, 10000000]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bounds<T>(pub T, pub T);
+
+impl<T: SampleUniform + PartialOrd + Copy> Bounds<T> {
+    /// Draws a value uniformly from the range.
+    fn sample<R: Rng>(&self, rng: &mut R) -> T {
+        rng.gen_range(self.0..=self.1)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.0 <= self.1
+    }
+}
+
+/// One task variant together with the ranges its parameters are drawn from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VariantSpec {
+    Compute { a: Bounds<i32>, b: Bounds<i32> },
+    Fibonacci { n: Bounds<u32> },
+    Divide { numerator: Bounds<i32>, denominator: Bounds<i32> },
+    Multiply { a: Bounds<i32>, b: Bounds<i32> },
+    Factorial { n: Bounds<u32> },
+    PrimeCheck { n: Bounds<u32> },
+    ModuloExponentiation { base: Bounds<u64>, exponent: Bounds<u64>, modulus: Bounds<u64> },
+}
+
+/// A variant and how often it is picked relative to the others.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightedVariant {
+    pub weight: u32,
+    #[serde(flatten)]
+    pub variant: VariantSpec,
+}
+
+/// Describes the mix of tasks a batch is generated from.
+///
+/// Each generated task picks a variant with probability proportional to its weight,
+/// then draws every parameter uniformly from that variant's ranges.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkloadSpec {
+    pub variants: Vec<WeightedVariant>,
+}
+
+/// Everything needed to regenerate a batch of tasks exactly.
+///
+/// A manifest is written next to benchmark results so that any run can be repeated
+/// later on the identical workload.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkloadManifest {
+    pub seed: u64,
+    pub batch_size: u32,
+    pub spec: WorkloadSpec,
+}
+
+impl VariantSpec {
+    /// Every variant with the default parameter ranges, in `TaskType` order.
+    pub fn defaults() -> Vec<VariantSpec> {
+        vec![
+            VariantSpec::Compute { a: Bounds(1, 99), b: Bounds(1, 99) },
+            VariantSpec::Fibonacci { n: Bounds(1, 29) },
+            // Avoid division by zero
+            VariantSpec::Divide { numerator: Bounds(1, 99), denominator: Bounds(2, 99) },
+            VariantSpec::Multiply { a: Bounds(1, 99), b: Bounds(1, 99) },
+            VariantSpec::Factorial { n: Bounds(0, 19) },
+            VariantSpec::PrimeCheck { n: Bounds(1, 99) },
+            // Ensure non-zero modulus
+            VariantSpec::ModuloExponentiation { base: Bounds(2, 19), exponent: Bounds(2, 9), modulus: Bounds(2, 50) },
+        ]
+    }
+
+    /// The position of the variant in declaration order, which matches `TaskType::index`.
+    pub fn index(&self) -> usize {
+        match self {
+            VariantSpec::Compute { .. } => 0,
+            VariantSpec::Fibonacci { .. } => 1,
+            VariantSpec::Divide { .. } => 2,
+            VariantSpec::Multiply { .. } => 3,
+            VariantSpec::Factorial { .. } => 4,
+            VariantSpec::PrimeCheck { .. } => 5,
+            VariantSpec::ModuloExponentiation { .. } => 6,
+        }
+    }
+
+    /// The snake_case name used in spec files and `--mix`.
+    pub fn key(&self) -> &'static str {
+        TaskType::ALL_KEYS[self.index()]
+    }
+
+    /// Draws one task of this variant.
+    fn sample<R: Rng>(&self, rng: &mut R) -> TaskType {
+        match self {
+            VariantSpec::Compute { a, b } => TaskType::Compute { a: a.sample(rng), b: b.sample(rng) },
+            VariantSpec::Fibonacci { n } => TaskType::Fibonacci { n: n.sample(rng) },
+            VariantSpec::Divide { numerator, denominator } => TaskType::Divide {
+                numerator: numerator.sample(rng),
+                denominator: denominator.sample(rng),
+            },
+            VariantSpec::Multiply { a, b } => TaskType::Multiply { a: a.sample(rng), b: b.sample(rng) },
+            VariantSpec::Factorial { n } => TaskType::Factorial { n: n.sample(rng) },
+            VariantSpec::PrimeCheck { n } => TaskType::PrimeCheck { n: n.sample(rng) },
+            VariantSpec::ModuloExponentiation { base, exponent, modulus } => TaskType::ModuloExponentiation {
+                base: base.sample(rng),
+                exponent: exponent.sample(rng),
+                modulus: modulus.sample(rng),
+            },
+        }
+    }
+
+    /// Checks that every range has `min <= max`.
+    fn validate(&self) -> Result<(), String> {
+        let valid = match self {
+            VariantSpec::Compute { a, b } | VariantSpec::Multiply { a, b } => a.is_valid() && b.is_valid(),
+            VariantSpec::Divide { numerator, denominator } => numerator.is_valid() && denominator.is_valid(),
+            VariantSpec::Fibonacci { n } | VariantSpec::Factorial { n } | VariantSpec::PrimeCheck { n } => n.is_valid(),
+            VariantSpec::ModuloExponentiation { base, exponent, modulus } => {
+                base.is_valid() && exponent.is_valid() && modulus.is_valid()
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Every range of '{}' must have min <= max.", self.key()))
+        }
+    }
+
+    /// Overrides the range of one parameter, e.g. `n` with `1..10000000`.
+    fn set_range(&mut self, param: &str, range: &str) -> Result<(), String> {
+        let key = self.key();
+        match (self, param) {
+            (VariantSpec::Compute { a, .. } | VariantSpec::Multiply { a, .. }, "a") => *a = parse_bounds(range)?,
+            (VariantSpec::Compute { b, .. } | VariantSpec::Multiply { b, .. }, "b") => *b = parse_bounds(range)?,
+            (VariantSpec::Divide { numerator, .. }, "numerator") => *numerator = parse_bounds(range)?,
+            (VariantSpec::Divide { denominator, .. }, "denominator") => *denominator = parse_bounds(range)?,
+            (
+                VariantSpec::Fibonacci { n } | VariantSpec::Factorial { n } | VariantSpec::PrimeCheck { n },
+                "n",
+            ) => *n = parse_bounds(range)?,
+            (VariantSpec::ModuloExponentiation { base, .. }, "base") => *base = parse_bounds(range)?,
+            (VariantSpec::ModuloExponentiation { exponent, .. }, "exponent") => *exponent = parse_bounds(range)?,
+            (VariantSpec::ModuloExponentiation { modulus, .. }, "modulus") => *modulus = parse_bounds(range)?,
+            (_, other) => return Err(format!("'{}' has no parameter '{}'.", key, other)),
+        }
+        Ok(())
+    }
+}
+
+/// Parses an inclusive range written as `min..max`, or a single value.
+fn parse_bounds<T: FromStr + Copy>(raw: &str) -> Result<Bounds<T>, String> {
+    let parse = |value: &str| value.trim().parse::<T>().map_err(|_| format!("Invalid range '{}'.", raw));
+    match raw.split_once("..") {
+        Some((min, max)) => Ok(Bounds(parse(min)?, parse(max)?)),
+        None => {
+            let value = parse(raw)?;
+            Ok(Bounds(value, value))
+        }
+    }
+}
+
+impl WorkloadSpec {
+    /// Every variant equally likely with the default parameter ranges.
+    pub fn uniform() -> WorkloadSpec {
+        WorkloadSpec {
+            variants: VariantSpec::defaults()
+                .into_iter()
+                .map(|variant| WeightedVariant { weight: 1, variant })
+                .collect(),
+        }
+    }
+
+    /// Parses a compact mix such as `prime_check:80:n=1..10000000,fibonacci:20`.
+    ///
+    /// Each comma-separated entry is `variant:weight` followed by optional
+    /// `:param=min..max` overrides; parameters that are not overridden keep their
+    /// default ranges.
+    pub fn parse_mix(mix: &str) -> Result<WorkloadSpec, String> {
+        let mut variants = Vec::new();
+        for entry in mix.split(',') {
+            let mut parts = entry.trim().split(':');
+            let key = parts.next().unwrap_or_default();
+            let mut variant = VariantSpec::defaults()
+                .into_iter()
+                .find(|variant| variant.key() == key)
+                .ok_or_else(|| format!("Unknown task variant '{}'.", key))?;
+            let weight = parts
+                .next()
+                .and_then(|weight| weight.parse().ok())
+                .ok_or_else(|| format!("Missing or invalid weight for '{}'.", key))?;
+            for param in parts {
+                let (name, range) = param
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected param=min..max, got '{}'.", param))?;
+                variant.set_range(name, range)?;
+            }
+            variants.push(WeightedVariant { weight, variant });
+        }
+        let spec = WorkloadSpec { variants };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Reads a spec from a JSON file.
+    pub fn load(path: &Path) -> Result<WorkloadSpec, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read workload spec '{}': {}", path.display(), e))?;
+        let spec: WorkloadSpec = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid workload spec '{}': {}", path.display(), e))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Checks that at least one variant can be picked and every range is well-formed.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.total_weight() == 0 {
+            return Err("A workload spec needs at least one variant with a non-zero weight.".into());
+        }
+        self.variants.iter().try_for_each(|entry| entry.variant.validate())
+    }
+
+    /// The sum of every variant's weight, as a `u64` so that large `u32` weights cannot overflow.
+    fn total_weight(&self) -> u64 {
+        self.variants.iter().map(|entry| u64::from(entry.weight)).sum()
+    }
+
+    /// Picks a variant with probability proportional to its weight.
+    fn pick<R: Rng>(&self, rng: &mut R) -> &VariantSpec {
+        let mut remaining = rng.gen_range(0..self.total_weight());
+        for entry in &self.variants {
+            if remaining < u64::from(entry.weight) {
+                return &entry.variant;
+            }
+            remaining -= u64::from(entry.weight);
+        }
+        unreachable!() // `remaining` is always below the total weight
+    }
+}
+
+impl fmt::Display for WorkloadSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == WorkloadSpec::uniform() {
+            return write!(f, "uniform mix");
+        }
+        let total = self.total_weight() as f64;
+        let shares: Vec<String> = self
+            .variants
+            .iter()
+            .filter(|entry| entry.weight > 0)
+            .map(|entry| format!("{:.0}% {}", entry.weight as f64 * 100.0 / total, entry.variant.key()))
+            .collect();
+        write!(f, "{}", shares.join(", "))
+    }
+}
+
+impl WorkloadManifest {
+    /// Generates the batch this manifest describes.
+    pub fn generate(&self) -> Vec<TaskType> {
+        generate_tasks(&self.spec, self.batch_size, self.seed)
+    }
+
+    /// Reads a manifest previously written by `save`.
+    pub fn load(path: &Path) -> Result<WorkloadManifest, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read manifest '{}': {}", path.display(), e))?;
+        let manifest: WorkloadManifest = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid manifest '{}': {}", path.display(), e))?;
+        manifest.spec.validate()?;
+        Ok(manifest)
+    }
+
+    /// Writes the manifest as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).expect("Manifest is always serializable");
+        fs::write(path, json + "\n").map_err(|e| format!("Failed to write manifest '{}': {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for WorkloadManifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} tasks, seed {}, {}", self.batch_size, self.seed, self.spec)
+    }
+}
+
+/// Generates a list of random tasks following a `WorkloadSpec`.
+/// On each iteration it picks a task variant according to the spec's weights,
+/// and draws the variant's input values from the spec's ranges.
+///
+/// # Arguments
+/// * `spec` - The variant weights and parameter ranges to draw from; must be valid.
+/// * `batch_size` - The total number of tasks to generate.
+/// * `seed` - Seed for the RNG; the same seed always yields the same batch.
+///
+/// # Returns
+/// A `Vec<TaskType>` containing `batch_size` randomly generated tasks.
+pub fn generate_tasks(spec: &WorkloadSpec, batch_size: u32, seed: u64) -> Vec<TaskType> {
+    // Use a seeded RNG for deterministic task generation
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    (0..batch_size)
+        .map(|_| spec.pick(&mut rng).sample(&mut rng))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_batch() {
+        let spec = WorkloadSpec::uniform();
+        assert_eq!(generate_tasks(&spec, 50, 7), generate_tasks(&spec, 50, 7));
+        assert_ne!(generate_tasks(&spec, 50, 7), generate_tasks(&spec, 50, 8));
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = WorkloadManifest {
+            seed: 9,
+            batch_size: 25,
+            spec: WorkloadSpec::parse_mix("prime_check:3:n=10..20,divide:1").unwrap(),
+        };
+        let path = std::env::temp_dir().join(format!("manifest-{}.json", std::process::id()));
+        manifest.save(&path).unwrap();
+        let loaded = WorkloadManifest::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(loaded.generate(), manifest.generate());
+    }
+
+    #[test]
+    fn test_weighted_mix() {
+        let spec = WorkloadSpec::parse_mix("prime_check:80:n=1..10000000,fibonacci:20").unwrap();
+        let tasks = generate_tasks(&spec, 10_000, 1);
+        let primes = tasks.iter().filter(|task| matches!(task, TaskType::PrimeCheck { .. })).count();
+        let fibs = tasks.iter().filter(|task| matches!(task, TaskType::Fibonacci { n } if *n <= 29)).count();
+        assert_eq!(primes + fibs, tasks.len());
+        assert!((7_700..8_300).contains(&primes), "got {} prime checks", primes);
+        assert!(tasks.iter().any(|task| matches!(task, TaskType::PrimeCheck { n } if *n > 1_000_000)));
+    }
+
+    #[test]
+    fn test_large_weights() {
+        let spec = WorkloadSpec::parse_mix("compute:4294967295,fibonacci:4294967295").unwrap();
+        assert!(spec.validate().is_ok());
+        let tasks = generate_tasks(&spec, 1_000, 1);
+        let computes = tasks.iter().filter(|task| matches!(task, TaskType::Compute { .. })).count();
+        assert!((400..600).contains(&computes), "got {} computes", computes);
+        assert_eq!(spec.to_string(), "50% compute, 50% fibonacci");
+    }
+
+    #[test]
+    fn test_variants_follow_task_types() {
+        let defaults = VariantSpec::defaults();
+        assert_eq!(defaults.len(), TaskType::ALL_KEYS.len());
+        let mut rng = StdRng::seed_from_u64(0);
+        for (index, variant) in defaults.iter().enumerate() {
+            assert_eq!(variant.index(), index);
+            assert_eq!(variant.sample(&mut rng).index(), index);
+            assert_eq!(serde_json::to_value(variant).unwrap()["type"], variant.key());
+        }
+    }
+
+    #[test]
+    fn test_spec_json_format() {
+        let json = r#"{"variants": [{"type": "compute", "weight": 2, "a": [5, 5], "b": [1, 3]}]}"#;
+        let spec: WorkloadSpec = serde_json::from_str(json).unwrap();
+        assert_eq!(spec, WorkloadSpec::parse_mix("compute:2:a=5:b=1..3").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_mixes() {
+        assert!(WorkloadSpec::parse_mix("sorting:1").is_err());
+        assert!(WorkloadSpec::parse_mix("compute").is_err());
+        assert!(WorkloadSpec::parse_mix("compute:0").is_err());
+        assert!(WorkloadSpec::parse_mix("compute:1:n=1..2").is_err());
+        assert!(WorkloadSpec::parse_mix("fibonacci:1:n=9..3").is_err());
+        assert!(WorkloadSpec::parse_mix("fibonacci:1:n=-1..3").is_err());
+    }
+}